@@ -0,0 +1,79 @@
+use std::hint::black_box;
+use std::time::Instant;
+
+use crotainers_rs::maps::CroBTree;
+
+const N: u64 = 200_000;
+
+fn keys() -> Vec<u64> {
+    let mut seed: u64 = 0x9e37_79b9_7f4a_7c15;
+    (0..N)
+        .map(|_| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            seed >> 16
+        })
+        .collect()
+}
+
+fn report(name: &str, ops: u64, start: Instant) {
+    let elapsed = start.elapsed();
+    let per_op = elapsed.as_nanos() as f64 / ops as f64;
+    println!("{:<28} {:>10.2?} {:>8.1} ns/op", name, elapsed, per_op);
+}
+
+fn main() {
+    let keys = keys();
+
+    let start = Instant::now();
+    let mut tree = CroBTree::new();
+    for &key in &keys {
+        tree.insert(key, key);
+    }
+    report("insert random", N, start);
+
+    let start = Instant::now();
+    let mut sequential = CroBTree::new();
+    for key in 0..N {
+        sequential.insert(key, key);
+    }
+    report("insert sequential", N, start);
+
+    let start = Instant::now();
+    let bulk: CroBTree<u64, u64> = CroBTree::from_sorted_iter((0..N).map(|key| (key, key)));
+    black_box(bulk.len());
+    report("from_sorted_iter", N, start);
+
+    let start = Instant::now();
+    let mut hits = 0u64;
+    for &key in &keys {
+        if let Some(value) = tree.get(black_box(&key)) {
+            hits += *value & 1;
+        }
+    }
+    black_box(hits);
+    report("get hit", N, start);
+
+    let start = Instant::now();
+    let mut misses = 0u64;
+    for &key in &keys {
+        if tree.get(black_box(&(key + 1))).is_none() {
+            misses += 1;
+        }
+    }
+    black_box(misses);
+    report("get miss", N, start);
+
+    let start = Instant::now();
+    let mut sum = 0u64;
+    for (key, _) in tree.iter() {
+        sum = sum.wrapping_add(*key);
+    }
+    black_box(sum);
+    report("iter", N, start);
+
+    let start = Instant::now();
+    for &key in &keys {
+        tree.remove(&key);
+    }
+    report("remove random", N, start);
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crotainers_rs::hash::{CroDetMap, CroFxMap};
+use crotainers_rs::maps::CroMap;
+
+const N: u64 = 200_000;
+
+fn keys() -> Vec<u64> {
+    let mut seed: u64 = 0x9e37_79b9_7f4a_7c15;
+    (0..N)
+        .map(|_| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            seed >> 16
+        })
+        .collect()
+}
+
+fn report(name: &str, ops: u64, start: Instant) {
+    let elapsed = start.elapsed();
+    let per_op = elapsed.as_nanos() as f64 / ops as f64;
+    println!("{:<28} {:>10.2?} {:>8.1} ns/op", name, elapsed, per_op);
+}
+
+fn main() {
+    let keys = keys();
+
+    let start = Instant::now();
+    let mut map = CroMap::new();
+    for &key in &keys {
+        map.insert(key, key);
+    }
+    report("insert random", N, start);
+
+    let start = Instant::now();
+    let mut hits = 0u64;
+    for &key in &keys {
+        if let Some(value) = map.get(black_box(&key)) {
+            hits += *value & 1;
+        }
+    }
+    black_box(hits);
+    report("get hit", N, start);
+
+    let start = Instant::now();
+    let mut misses = 0u64;
+    for &key in &keys {
+        if map.get(black_box(&(key ^ 1 << 60))).is_none() {
+            misses += 1;
+        }
+    }
+    black_box(misses);
+    report("get miss", N, start);
+
+    let start = Instant::now();
+    let mut sum = 0u64;
+    for (key, _) in map.iter() {
+        sum = sum.wrapping_add(*key);
+    }
+    black_box(sum);
+    report("iter", N, start);
+
+    let start = Instant::now();
+    for (i, &key) in keys.iter().enumerate() {
+        map.remove(&key);
+        map.insert(key ^ 1 << 61, i as u64);
+    }
+    report("remove + insert churn", 2 * N, start);
+
+    let start = Instant::now();
+    for &key in &keys {
+        map.remove(&(key ^ 1 << 61));
+    }
+    report("remove random", N, start);
+
+    let start = Instant::now();
+    let mut strings = CroMap::new();
+    for &key in &keys {
+        strings.insert(key.to_string(), key);
+    }
+    let mut found = 0u64;
+    for &key in &keys {
+        found += strings.get(key.to_string().as_str()).is_some() as u64;
+    }
+    black_box(found);
+    report("string insert + get", 2 * N, start);
+
+    let start = Instant::now();
+    let mut fx: CroFxMap<u64, u64> = CroFxMap::default();
+    for &key in &keys {
+        fx.insert(key, key);
+    }
+    let mut hits = 0u64;
+    for &key in &keys {
+        hits += fx.get(black_box(&key)).is_some() as u64;
+    }
+    black_box(hits);
+    report("fx insert + get", 2 * N, start);
+
+    let start = Instant::now();
+    let mut det: CroDetMap<u64, u64> = CroDetMap::default();
+    for &key in &keys {
+        det.insert(key, key);
+    }
+    let mut hits = 0u64;
+    for &key in &keys {
+        hits += det.get(black_box(&key)).is_some() as u64;
+    }
+    black_box(hits);
+    report("det insert + get", 2 * N, start);
+
+    for incremental in [false, true] {
+        let mut map = CroMap::new();
+        map.set_incremental_rehash(incremental);
+        let mut worst = Duration::ZERO;
+        let start = Instant::now();
+        for key in 0..4 * N {
+            let before = Instant::now();
+            map.insert(key, key);
+            worst = worst.max(before.elapsed());
+        }
+        let name = if incremental { "insert incremental" } else { "insert one-shot rehash" };
+        report(name, 4 * N, start);
+        println!("{:<28} {:>10.2?}", "  worst single insert", worst);
+    }
+
+    let start = Instant::now();
+    let mut std_map = HashMap::new();
+    for &key in &keys {
+        std_map.insert(key, key);
+    }
+    report("std insert random", N, start);
+
+    let start = Instant::now();
+    let mut hits = 0u64;
+    for &key in &keys {
+        if let Some(value) = std_map.get(black_box(&key)) {
+            hits += *value & 1;
+        }
+    }
+    black_box(hits);
+    report("std get hit", N, start);
+}
@@ -0,0 +1,41 @@
+use std::cmp::Ordering;
+
+// Total order used by the ordered containers in place of `Ord`. Containers
+// clone their comparator when they split, so it should be cheap to copy.
+pub trait Comparator<T: ?Sized>: Clone {
+    fn compare(&self, a: &T, b: &T) -> Ordering;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CroNaturalOrder;
+
+impl<T> Comparator<T> for CroNaturalOrder
+where
+    T: Ord + ?Sized,
+{
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CroReverseOrder;
+
+impl<T> Comparator<T> for CroReverseOrder
+where
+    T: Ord + ?Sized,
+{
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        b.cmp(a)
+    }
+}
+
+impl<T, F> Comparator<T> for F
+where
+    T: ?Sized,
+    F: Fn(&T, &T) -> Ordering + Clone,
+{
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self(a, b)
+    }
+}
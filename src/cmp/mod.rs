@@ -0,0 +1,2 @@
+mod comparator;
+pub use comparator::{Comparator, CroNaturalOrder, CroReverseOrder};
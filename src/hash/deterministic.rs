@@ -0,0 +1,28 @@
+use std::hash::BuildHasher;
+
+use super::sip::CroSipHasher13;
+
+// SipHash-1-3 keyed from a fixed seed. The same seed gives the same hashes,
+// and so the same iteration order, on every run and platform.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CroDetState {
+    seed: u64,
+}
+
+impl CroDetState {
+    pub fn with_seed(seed: u64) -> Self {
+        CroDetState { seed }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl BuildHasher for CroDetState {
+    type Hasher = CroSipHasher13;
+
+    fn build_hasher(&self) -> CroSipHasher13 {
+        CroSipHasher13::new_with_keys(self.seed, self.seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+}
@@ -0,0 +1,68 @@
+use std::hash::{BuildHasherDefault, Hasher};
+
+// Multiply-rotate hash from Firefox and rustc. One multiply per word makes it
+// much faster than SipHash for integer keys, but it has no key, so it should
+// only be used where the keys are not attacker controlled.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CroFxHasher {
+    hash: u64,
+}
+
+pub type CroFxState = BuildHasherDefault<CroFxHasher>;
+
+const K: u64 = 0x517c_c1b7_2722_0a95;
+
+impl CroFxHasher {
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(K);
+    }
+}
+
+impl Hasher for CroFxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.add(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            self.add(load_le(rest));
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.add(i as u64);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.add(i as u64);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.add(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.add(i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.add(i as u64);
+    }
+
+    // The product's low bits only depend on the input's low bits, and the
+    // tables index buckets by the low bits of the hash, so the well-mixed
+    // high half is rotated down.
+    fn finish(&self) -> u64 {
+        self.hash.rotate_left(26)
+    }
+}
+
+// Little-endian load of up to eight bytes.
+pub(crate) fn load_le(bytes: &[u8]) -> u64 {
+    let mut word = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        word |= (byte as u64) << (8 * i);
+    }
+    word
+}
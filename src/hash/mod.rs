@@ -0,0 +1,14 @@
+mod deterministic;
+mod fx;
+mod sip;
+pub use deterministic::CroDetState;
+pub use fx::{CroFxHasher, CroFxState};
+pub use sip::{CroSipHasher13, CroSipState};
+
+use crate::maps::CroMap;
+use crate::sets::CroHashSet;
+
+pub type CroFxMap<K, V> = CroMap<K, V, CroFxState>;
+pub type CroFxSet<K> = CroHashSet<K, CroFxState>;
+pub type CroDetMap<K, V> = CroMap<K, V, CroDetState>;
+pub type CroDetSet<K> = CroHashSet<K, CroDetState>;
@@ -0,0 +1,156 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use super::fx::load_le;
+
+// SipHash-1-3: one compression round per message word and three finalization
+// rounds, the variant std uses for `HashMap`. Integers are fed in
+// little-endian order so hashes agree across platforms.
+#[derive(Clone, Copy, Debug)]
+pub struct CroSipHasher13 {
+    v0: u64,
+    v1: u64,
+    v2: u64,
+    v3: u64,
+    // Bytes of an unfinished word, and how many there are.
+    tail: u64,
+    ntail: usize,
+    length: usize,
+}
+
+// Builds keyed SipHash-1-3 hashers. `new` draws fresh keys for every state.
+#[derive(Clone, Copy, Debug)]
+pub struct CroSipState {
+    k0: u64,
+    k1: u64,
+}
+
+impl CroSipHasher13 {
+    pub fn new_with_keys(k0: u64, k1: u64) -> Self {
+        CroSipHasher13 {
+            v0: k0 ^ 0x736f_6d65_7073_6575,
+            v1: k1 ^ 0x646f_7261_6e64_6f6d,
+            v2: k0 ^ 0x6c79_6765_6e65_7261,
+            v3: k1 ^ 0x7465_6462_7974_6573,
+            tail: 0,
+            ntail: 0,
+            length: 0,
+        }
+    }
+
+    fn round(&mut self) {
+        self.v0 = self.v0.wrapping_add(self.v1);
+        self.v1 = self.v1.rotate_left(13);
+        self.v1 ^= self.v0;
+        self.v0 = self.v0.rotate_left(32);
+        self.v2 = self.v2.wrapping_add(self.v3);
+        self.v3 = self.v3.rotate_left(16);
+        self.v3 ^= self.v2;
+        self.v0 = self.v0.wrapping_add(self.v3);
+        self.v3 = self.v3.rotate_left(21);
+        self.v3 ^= self.v0;
+        self.v2 = self.v2.wrapping_add(self.v1);
+        self.v1 = self.v1.rotate_left(17);
+        self.v1 ^= self.v2;
+        self.v2 = self.v2.rotate_left(32);
+    }
+
+    fn compress(&mut self, word: u64) {
+        self.v3 ^= word;
+        self.round();
+        self.v0 ^= word;
+    }
+}
+
+impl Hasher for CroSipHasher13 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.length += bytes.len();
+        let mut bytes = bytes;
+        if self.ntail != 0 {
+            let needed = 8 - self.ntail;
+            let fill = needed.min(bytes.len());
+            self.tail |= load_le(&bytes[..fill]) << (8 * self.ntail);
+            if fill < needed {
+                self.ntail += fill;
+                return;
+            }
+            self.compress(self.tail);
+            self.ntail = 0;
+            bytes = &bytes[fill..];
+        }
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.compress(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        let rest = chunks.remainder();
+        self.tail = load_le(rest);
+        self.ntail = rest.len();
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        // Word-aligned integers skip the byte shuffling.
+        if self.ntail == 0 {
+            self.length += 8;
+            self.compress(i);
+        } else {
+            self.write(&i.to_le_bytes());
+        }
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        let mut state = *self;
+        let last = ((self.length as u64 & 0xff) << 56) | self.tail;
+        state.compress(last);
+        state.v2 ^= 0xff;
+        state.round();
+        state.round();
+        state.round();
+        state.v0 ^ state.v1 ^ state.v2 ^ state.v3
+    }
+}
+
+impl CroSipState {
+    pub fn new() -> Self {
+        // std's RandomState already holds per-process random keys; hashing
+        // through it derives fresh ones without another entropy source.
+        let random = RandomState::new();
+        CroSipState {
+            k0: random.hash_one(0u64),
+            k1: random.hash_one(1u64),
+        }
+    }
+
+    pub fn with_keys(k0: u64, k1: u64) -> Self {
+        CroSipState { k0, k1 }
+    }
+
+    pub fn keys(&self) -> (u64, u64) {
+        (self.k0, self.k1)
+    }
+}
+
+impl Default for CroSipState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BuildHasher for CroSipState {
+    type Hasher = CroSipHasher13;
+
+    fn build_hasher(&self) -> CroSipHasher13 {
+        CroSipHasher13::new_with_keys(self.k0, self.k1)
+    }
+}
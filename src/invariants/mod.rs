@@ -0,0 +1,97 @@
+use std::error::Error;
+use std::fmt;
+
+// First broken invariant found by a container's `validate`. Positions are
+// buckets for hash tables, node positions for lists and heights (leaves at
+// zero) for trees.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CroInvariantError {
+    SizeExceedsCap { size: usize, cap: usize },
+    // A capacity with no buffer behind it.
+    MissingBuffer { cap: usize },
+    HeadOutOfRange { head: usize, size: usize, cap: usize },
+    // The node at `index` is not linked back to its neighbour.
+    BrokenLink { index: usize },
+    // A stored count disagrees with the entries actually there.
+    LenMismatch { stored: usize, counted: usize },
+    MirrorMismatch { bucket: usize },
+    GrowthMismatch { stored: usize, expected: usize },
+    HashMismatch { bucket: usize },
+    // An EMPTY bucket on the probe sequence before the entry hides it from
+    // lookups.
+    UnreachableEntry { bucket: usize },
+    // An entry left behind in the part of the old table already rehashed.
+    UnmovedEntry { bucket: usize },
+    // A position in the index table that is out of range or repeated.
+    BadIndex { index: usize },
+    NodeOccupancy { height: usize, len: usize, min: usize, max: usize },
+    KeysOutOfOrder { height: usize },
+    StaleParentLink { height: usize },
+}
+
+impl fmt::Display for CroInvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            CroInvariantError::SizeExceedsCap { size, cap } => {
+                write!(f, "size {} exceeds capacity {}", size, cap)
+            }
+            CroInvariantError::MissingBuffer { cap } => {
+                write!(f, "capacity {} with no buffer", cap)
+            }
+            CroInvariantError::HeadOutOfRange { head, size, cap } => {
+                write!(f, "head {} with size {} does not fit capacity {}", head, size, cap)
+            }
+            CroInvariantError::BrokenLink { index } => {
+                write!(f, "links disagree at node {}", index)
+            }
+            CroInvariantError::LenMismatch { stored, counted } => {
+                write!(f, "stored length {} but found {} entries", stored, counted)
+            }
+            CroInvariantError::MirrorMismatch { bucket } => {
+                write!(f, "mirrored control byte of bucket {} differs", bucket)
+            }
+            CroInvariantError::GrowthMismatch { stored, expected } => {
+                write!(f, "growth left is {} but should be {}", stored, expected)
+            }
+            CroInvariantError::HashMismatch { bucket } => {
+                write!(f, "control byte of bucket {} does not match its hash", bucket)
+            }
+            CroInvariantError::UnreachableEntry { bucket } => {
+                write!(f, "entry in bucket {} sits past an empty bucket on its probe", bucket)
+            }
+            CroInvariantError::UnmovedEntry { bucket } => {
+                write!(f, "old bucket {} is still full after being rehashed", bucket)
+            }
+            CroInvariantError::BadIndex { index } => {
+                write!(f, "index {} is out of range or repeated", index)
+            }
+            CroInvariantError::NodeOccupancy { height, len, min, max } => write!(
+                f,
+                "node at height {} holds {} entries, outside {}..={}",
+                height, len, min, max
+            ),
+            CroInvariantError::KeysOutOfOrder { height } => {
+                write!(f, "keys out of order at height {}", height)
+            }
+            CroInvariantError::StaleParentLink { height } => {
+                write!(f, "stale parent link below height {}", height)
+            }
+        }
+    }
+}
+
+impl Error for CroInvariantError {}
+
+// Runs a container's check after a mutation when the `debug-invariants`
+// feature is on, and panics on the first broken invariant.
+macro_rules! check_invariants {
+    ($check:expr) => {
+        if cfg!(feature = "debug-invariants") {
+            if let Err(err) = $check {
+                panic!("invariant violated: {}", err);
+            }
+        }
+    };
+}
+
+pub(crate) use check_invariants;
@@ -1,7 +1,13 @@
+pub mod cmp;
 mod etc;
-mod maps;
-mod sequences;
-mod sets;
+pub mod hash;
+pub mod invariants;
+pub mod maps;
+pub mod prelude;
+pub mod sequences;
+pub mod sets;
+pub mod std_compat;
+pub mod traits;
 
 
 #[cfg(test)]
@@ -13,7 +19,7 @@ mod tests {
         CroLList
     };
 
-    use crate::maps::{CroBTree, CroMap};
+    use crate::maps::{CroBTree, CroBTreeEntry, CroMap, CroMapRawEntryMut};
     use crate::sets::{CroBTreeSet, CroHashSet};
 
     #[test]
@@ -120,6 +126,36 @@ mod tests {
         drop(vec);
     }
 
+    #[test]
+    fn test_vec_remove_and_drain_drop_once() {
+        use std::rc::Rc;
+
+        let live = Rc::new(());
+        let mut vec = CroVec::new();
+        for _ in 0..10 {
+            vec.push(Rc::clone(&live));
+        }
+
+        // Removing from the front or middle shifts the tail down; the slot it
+        // vacates at the end must not be dropped a second time.
+        drop(vec.remove(0));
+        drop(vec.remove(4));
+        assert_eq!(Rc::strong_count(&live), 9);
+
+        let drained: Vec<Rc<()>> = vec.drain(1..4).collect();
+        assert_eq!(drained.len(), 3);
+        assert_eq!(Rc::strong_count(&live), 9);
+        drop(drained);
+        assert_eq!(Rc::strong_count(&live), 6);
+
+        vec.drain(..2).next();
+        assert_eq!(vec.size(), 3);
+        assert_eq!(Rc::strong_count(&live), 4);
+
+        drop(vec);
+        assert_eq!(Rc::strong_count(&live), 1);
+    }
+
     #[test]
     fn test_iter() {
         let mut vec = CroVec::new();
@@ -458,7 +494,7 @@ mod tests {
     fn test_complex_collision_scenario() {
         let mut map = CroMap::new();
 
-        let items = vec![
+        let items = [
             ("abc", 1),
             ("bac", 2),
             ("cba", 3),
@@ -545,6 +581,806 @@ mod tests {
         assert_eq!(map.get(&1), Some(&10));
     }
 
+    // Keeps only a few bits of the key, so long probe runs and collisions
+    // of the seven-bit hash fragment are routine.
+    #[derive(Default, Clone)]
+    struct CoarseHasher(u64);
+
+    impl std::hash::Hasher for CoarseHasher {
+        fn finish(&self) -> u64 {
+            (self.0 % 61).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for &byte in bytes {
+                self.0 = self.0.wrapping_mul(31).wrapping_add(byte as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn test_map_matches_std_under_churn() {
+        use std::collections::HashMap;
+        use std::hash::BuildHasherDefault;
+        use std::rc::Rc;
+
+        let live = Rc::new(());
+        let mut seed: u64 = 0x0040_5eed_0bad_cafe;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            seed >> 33
+        };
+        for span in [12, 300, 5000] {
+            let mut map: CroMap<u64, Rc<()>, BuildHasherDefault<CoarseHasher>> =
+                CroMap::with_cap_and_hasher(0, BuildHasherDefault::default());
+            // Keep the long probe runs rather than escaping them by reseeding.
+            map.set_probe_limit(None);
+            let mut std_map = HashMap::new();
+            for step in 0..8000 {
+                let key = next() % span;
+                match next() % 8 {
+                    0..=3 => {
+                        let old = map.insert(key, live.clone());
+                        assert_eq!(old.is_some(), std_map.insert(key, step).is_some());
+                    }
+                    4 | 5 => assert_eq!(map.remove(&key).is_some(), std_map.remove(&key).is_some()),
+                    6 => assert_eq!(map.get(&key).is_some(), std_map.contains_key(&key)),
+                    _ => {
+                        if step % 5 == 0 {
+                            map.retain(|k, _| k % 3 != key % 3);
+                            std_map.retain(|k, _| k % 3 != key % 3);
+                        } else if step % 7 == 0 {
+                            map.shrink_to_fit();
+                        }
+                    }
+                }
+                assert_eq!(map.size(), std_map.len());
+                assert_eq!(Rc::strong_count(&live), map.size() + 1);
+            }
+            let mut keys: Vec<u64> = map.iter().map(|(k, _)| *k).collect();
+            keys.sort_unstable();
+            let mut std_keys: Vec<u64> = std_map.keys().copied().collect();
+            std_keys.sort_unstable();
+            assert_eq!(keys, std_keys);
+            assert!(map.cap().is_power_of_two());
+
+            let mut drain = map.drain();
+            drain.next();
+            drop(drain);
+            assert!(map.is_empty());
+            assert_eq!(Rc::strong_count(&live), 1);
+
+            for key in 0..span {
+                map.insert(key, live.clone());
+            }
+            let mut into_iter = map.into_iter();
+            assert_eq!(into_iter.len(), span as usize);
+            into_iter.next();
+            assert_eq!(into_iter.len(), span as usize - 1);
+            drop(into_iter);
+            assert_eq!(Rc::strong_count(&live), 1);
+        }
+    }
+
+    #[test]
+    fn test_map_reseeds_on_long_probes() {
+        use std::hash::BuildHasherDefault;
+
+        #[derive(Default)]
+        struct ConstantHasher;
+
+        impl std::hash::Hasher for ConstantHasher {
+            fn finish(&self) -> u64 {
+                0x9e37_79b9_7f4a_7c15
+            }
+
+            fn write(&mut self, _: &[u8]) {}
+        }
+
+        let mut unguarded: CroMap<u64, u64, BuildHasherDefault<ConstantHasher>> = CroMap::default();
+        unguarded.set_probe_limit(None);
+        let mut map: CroMap<u64, u64, BuildHasherDefault<ConstantHasher>> = CroMap::default();
+        map.set_incremental_rehash(true);
+        for key in 0..1000 {
+            unguarded.insert(key, key);
+            map.insert(key, key * 2);
+        }
+        let stats = unguarded.stats();
+        assert_eq!(stats.reseeds, 0);
+        assert!(stats.max_probe > 32);
+
+        let stats = map.stats();
+        assert_eq!(stats.reseeds, 1);
+        assert_eq!(stats.size, 1000);
+        assert_eq!(stats.probe_limit, Some(32));
+        assert!(stats.max_probe <= 32);
+        for key in 0..1000 {
+            assert_eq!(map.get(&key), Some(&(key * 2)));
+        }
+        assert_eq!(map.remove(&7), Some(14));
+        assert_eq!(map.iter().count(), 999);
+
+        let mut fast: CroMap<u64, u64> = CroMap::new();
+        for key in 0..100_000 {
+            fast.insert(key, key);
+        }
+        assert_eq!(fast.stats().reseeds, 0);
+    }
+
+    #[test]
+    fn test_map_raw_api() {
+        use std::collections::HashMap;
+
+        let words = ["apple", "pear", "fig", "apple", "plum", "fig", "apple"];
+        for incremental in [false, true] {
+            let mut counts: CroMap<String, usize> = CroMap::with_cap(0);
+            counts.set_incremental_rehash(incremental);
+            for i in 0..2000 {
+                let word = if i < words.len() { words[i].to_string() } else { format!("w{}", i % 700) };
+                // Only a miss allocates an owned key.
+                match counts.raw_entry_mut().from_key(word.as_str()) {
+                    CroMapRawEntryMut::Occupied(mut entry) => *entry.get_mut() += 1,
+                    CroMapRawEntryMut::Vacant(entry) => {
+                        entry.insert(word.clone(), 1);
+                    }
+                }
+            }
+            assert_eq!(counts.size(), 4 + 700);
+            assert_eq!(counts.get("apple"), Some(&3));
+            assert_eq!(counts.get("w100"), Some(&3));
+            assert_eq!(counts.get("w5"), Some(&2));
+
+            // Hashes computed once are reused across lookups and removal.
+            let hash = counts.hash_of("fig");
+            assert_eq!(hash, counts.hash_of(&"fig".to_string()));
+            assert_eq!(counts.get_with_hash(hash, |key| key == "fig"), Some(&2));
+            let partial = counts.raw_entry().from_hash(hash, |key| key.starts_with('f'));
+            assert_eq!(partial, Some((&"fig".to_string(), &2)));
+            assert_eq!(counts.raw_entry().from_key_hashed_nocheck(hash, "fog"), None);
+            assert_eq!(counts.insert_with_hash(hash, "fig".to_string(), 10), Some(2));
+            let removed = counts.remove_with_hash(hash, |key| key == "fig");
+            assert_eq!(removed, Some(("fig".to_string(), 10)));
+            assert_eq!(counts.remove_with_hash(hash, |key| key == "fig"), None);
+            let hash = counts.hash_of("kiwi");
+            assert_eq!(counts.insert_with_hash(hash, "kiwi".to_string(), 1), None);
+
+            let (key, value) = counts.raw_entry_mut().from_key("kiwi").or_insert("kiwi".to_string(), 0);
+            assert_eq!((key.as_str(), *value), ("kiwi", 1));
+            let entry = counts.raw_entry_mut().from_key("w1").and_modify(|_, value| *value *= 10);
+            match entry {
+                CroMapRawEntryMut::Occupied(entry) => {
+                    assert_eq!(entry.remove_entry(), ("w1".to_string(), 20))
+                }
+                CroMapRawEntryMut::Vacant(_) => panic!("w1 should be present"),
+            }
+
+            let std_map: HashMap<String, usize> =
+                counts.iter().map(|(key, value)| (key.clone(), *value)).collect();
+            assert_eq!(std_map.len(), counts.size());
+            for (key, value) in &std_map {
+                assert_eq!(counts.raw_entry().from_key(key.as_str()), Some((key, value)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_map_scan_survives_resizing() {
+        use std::collections::HashSet;
+
+        for incremental in [false, true] {
+            let mut map: CroMap<u64, u64> = CroMap::with_cap(0);
+            map.set_incremental_rehash(incremental);
+            for key in 0..1000 {
+                map.insert(key, key);
+            }
+            let mut seen = HashSet::new();
+            let mut caps = HashSet::new();
+            let mut cursor = 0;
+            let mut calls = 0;
+            loop {
+                let (next, page) = map.scan(cursor, 40);
+                for (key, value) in page {
+                    assert_eq!(key, value);
+                    seen.insert(*key);
+                }
+                caps.insert(map.cap());
+                cursor = next;
+                calls += 1;
+                if cursor == 0 {
+                    break;
+                }
+                // Churn keys outside 0..500 while the scan is paused: grow
+                // the table several times over, then shrink it back.
+                match calls % 12 {
+                    0..=5 => {
+                        for key in 0..400 {
+                            map.insert(10_000 + calls * 400 + key, 10_000 + calls * 400 + key);
+                        }
+                    }
+                    6 => map.retain(|key, _| *key < 500 || key % 7 == 0),
+                    7 => map.shrink_to_fit(),
+                    _ => {
+                        map.remove(&(500 + calls));
+                    }
+                }
+            }
+            // The table was resized several times while the scan was paused.
+            assert!(caps.len() > 2);
+            for key in 0..500 {
+                assert!(seen.contains(&key), "missed {}", key);
+            }
+        }
+
+        let mut map: CroMap<u64, u64> = CroMap::new();
+        for key in 0..100 {
+            map.insert(key, key);
+        }
+        let (cursor, page) = map.scan(0, usize::MAX);
+        assert_eq!(cursor, 0);
+        assert_eq!(page.size(), 100);
+        let empty: CroMap<u64, u64> = CroMap::new();
+        assert_eq!(empty.scan(0, 10).0, 0);
+    }
+
+    #[test]
+    fn test_map_scan_survives_reseed() {
+        use std::collections::HashSet;
+        use std::hash::BuildHasherDefault;
+
+        // Spreads small keys and sends every key from `ATTACK` up to one bucket.
+        const ATTACK: u64 = 1 << 40;
+
+        #[derive(Default)]
+        struct SplitHasher(u64);
+
+        impl std::hash::Hasher for SplitHasher {
+            fn finish(&self) -> u64 {
+                if self.0 >= ATTACK {
+                    0x9e37_79b9_7f4a_7c15
+                } else {
+                    self.0.wrapping_mul(0x9e37_79b9_7f4a_7c15).rotate_left(29)
+                }
+            }
+
+            fn write(&mut self, _: &[u8]) {}
+
+            fn write_u64(&mut self, value: u64) {
+                self.0 = value;
+            }
+        }
+
+        for incremental in [false, true] {
+            let mut map: CroMap<u64, u64, BuildHasherDefault<SplitHasher>> = CroMap::default();
+            map.set_incremental_rehash(incremental);
+            for key in 0..1000 {
+                map.insert(key, key);
+            }
+            let mut seen = HashSet::new();
+            let mut cursor = 0;
+            let mut calls = 0;
+            loop {
+                let (next, page) = map.scan(cursor, 30);
+                for (key, _) in page {
+                    seen.insert(*key);
+                }
+                cursor = next;
+                calls += 1;
+                if cursor == 0 {
+                    break;
+                }
+                if calls == 5 {
+                    for key in 0..200 {
+                        map.insert(ATTACK + key, key);
+                    }
+                    assert_eq!(map.stats().reseeds, 1);
+                }
+                if calls > 5 {
+                    map.remove(&(ATTACK + calls));
+                }
+            }
+            assert!(calls > 5);
+            for key in 0..1000 {
+                assert!(seen.contains(&key), "missed {}", key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_map_incremental_rehash() {
+        use std::collections::HashMap;
+
+        let mut map: CroMap<u64, u64> = CroMap::with_cap(0);
+        map.set_incremental_rehash(true);
+        let mut std_map = HashMap::new();
+        let mut seen_rehash = false;
+        let mut progress = None;
+        for key in 0..5000u64 {
+            map.insert(key, key);
+            std_map.insert(key, key);
+            if key % 3 == 0 {
+                let gone = key / 2;
+                assert_eq!(map.remove(&gone), std_map.remove(&gone));
+            }
+            if let Some((moved, total)) = map.rehash_progress() {
+                seen_rehash = true;
+                assert!(moved < total);
+                // Each call moves a bounded number of buckets.
+                if let Some((before, before_total)) = progress {
+                    if before_total == total {
+                        assert!(moved - before <= 16);
+                    }
+                }
+                assert_eq!(map.get(&key), Some(&key));
+                assert_eq!(map.iter().count(), std_map.len());
+            }
+            progress = map.rehash_progress();
+            assert_eq!(map.size(), std_map.len());
+        }
+        assert!(seen_rehash);
+        for (key, value) in std_map.iter() {
+            assert_eq!(map.get(key), Some(value));
+        }
+
+        while map.rehash_progress().is_none() {
+            let key = map.size() as u64 * 7 + 1_000_000;
+            map.insert(key, key);
+            std_map.insert(key, key);
+        }
+        let mut keys: Vec<u64> = map.iter().map(|(k, _)| *k).collect();
+        keys.sort_unstable();
+        let mut std_keys: Vec<u64> = std_map.keys().copied().collect();
+        std_keys.sort_unstable();
+        assert_eq!(keys, std_keys);
+        map.set_incremental_rehash(false);
+        assert_eq!(map.rehash_progress(), None);
+        assert_eq!(map.size(), std_map.len());
+
+        map.set_incremental_rehash(true);
+        while map.rehash_progress().is_none() {
+            let key = map.size() as u64 * 7 + 1_000_000;
+            map.insert(key, key);
+        }
+        let size = map.size();
+        let mut drained: Vec<u64> = map.drain().map(|(k, _)| k).collect();
+        drained.sort_unstable();
+        drained.dedup();
+        assert_eq!(drained.len(), size);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_sip13_matches_std() {
+        use crate::hash::CroSipHasher13;
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::Hasher;
+
+        let data: Vec<u8> = (0..64u8).map(|b| b.wrapping_mul(37)).collect();
+        for len in 0..data.len() {
+            for split in [0, 1, 3, 7, 8, 9] {
+                let split = split.min(len);
+                let mut ours = CroSipHasher13::new_with_keys(0, 0);
+                let mut theirs = DefaultHasher::new();
+                ours.write(&data[..split]);
+                ours.write(&data[split..len]);
+                theirs.write(&data[..len]);
+                assert_eq!(ours.finish(), theirs.finish(), "len {} split {}", len, split);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hasher_family_maps() {
+        use crate::hash::{CroDetMap, CroDetSet, CroDetState, CroFxMap, CroFxSet, CroSipState};
+        use std::hash::BuildHasher;
+
+        // Fixed across runs and platforms.
+        assert_eq!(CroDetState::default().hash_one(42u64), 0x9e00_2767_669e_4966);
+        assert_ne!(CroDetState::with_seed(1).hash_one(42u64), CroDetState::default().hash_one(42u64));
+        let keyed = CroSipState::with_keys(3, 4);
+        assert_eq!(keyed.hash_one("key"), CroSipState::with_keys(3, 4).hash_one("key"));
+
+        let mut fx: CroFxMap<u64, u64> = CroFxMap::default();
+        let mut det: CroDetMap<u64, u64> = CroDetMap::with_cap_and_hasher(0, CroDetState::with_seed(7));
+        let mut again: CroDetMap<u64, u64> = CroDetMap::with_cap_and_hasher(0, CroDetState::with_seed(7));
+        for i in 0..2000u64 {
+            // Multiples of a large power of two only differ in their high bits.
+            fx.insert(i << 40, i);
+            fx.insert(i, i);
+            det.insert(i * 3, i);
+            again.insert(i * 3, i);
+        }
+        assert_eq!(fx.size(), 4000 - 1);
+        assert_eq!(fx.get(&(1999 << 40)), Some(&1999));
+        assert!(det.iter().eq(again.iter()));
+
+        let fx_set: CroFxSet<&str> = ["a", "b", "c"].iter().copied().collect();
+        assert!(fx_set.contains("b"));
+        let det_set: CroDetSet<u32> = (0..100).collect();
+        let order: Vec<u32> = det_set.iter().copied().collect();
+        let rebuilt: CroDetSet<u32> = (0..100).collect();
+        assert!(rebuilt.iter().copied().eq(order));
+    }
+
+    #[test]
+    fn test_index_map_matches_vec_model() {
+        use crate::maps::{CroIndexMap, CroIndexMapEntry};
+        use std::hash::BuildHasherDefault;
+
+        let mut seed: u64 = 0x1d3a_5eed_f00d_0042;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            seed >> 33
+        };
+        // Coarse hashes make the index table collide and fill with tombstones.
+        let mut map: CroIndexMap<u64, u64, BuildHasherDefault<CoarseHasher>> = CroIndexMap::default();
+        let mut model: Vec<(u64, u64)> = Vec::new();
+        for step in 0..6000u64 {
+            let key = next() % 300;
+            let position = model.iter().position(|&(k, _)| k == key);
+            match next() % 8 {
+                0..=2 => {
+                    let (index, old) = map.insert_full(key, step);
+                    match position {
+                        Some(i) => {
+                            assert_eq!((index, old), (i, Some(model[i].1)));
+                            model[i].1 = step;
+                        }
+                        None => {
+                            assert_eq!((index, old), (model.len(), None));
+                            model.push((key, step));
+                        }
+                    }
+                }
+                3 => {
+                    let removed = map.swap_remove_full(&key);
+                    assert_eq!(removed, position.map(|i| (i, key, model.swap_remove(i).1)));
+                }
+                4 => {
+                    let removed = map.shift_remove_full(&key);
+                    assert_eq!(removed, position.map(|i| (i, key, model.remove(i).1)));
+                }
+                5 if !model.is_empty() => {
+                    let from = next() as usize % model.len();
+                    let to = next() as usize % model.len();
+                    map.move_index(from, to);
+                    let entry = model.remove(from);
+                    model.insert(to, entry);
+                }
+                6 => match map.entry(key) {
+                    CroIndexMapEntry::Occupied(entry) => {
+                        assert_eq!(Some(entry.index()), position);
+                        assert_eq!(entry.swap_remove(), model.swap_remove(position.unwrap()).1);
+                    }
+                    CroIndexMapEntry::Vacant(entry) => {
+                        assert_eq!(position, None);
+                        assert_eq!(entry.index(), model.len());
+                        *entry.insert(0) += step;
+                        model.push((key, step));
+                    }
+                },
+                _ => {
+                    let index = next() as usize % (model.len() + 1);
+                    assert_eq!(map.get_index(index), model.get(index).map(|(k, v)| (k, v)));
+                    assert_eq!(map.get_index_of(&key), position);
+                }
+            }
+            if step % 500 == 499 {
+                map.sort_by(|_, v1, _, v2| v2.cmp(v1));
+                model.sort_by_key(|&(_, v)| std::cmp::Reverse(v));
+            }
+            assert_eq!(map.size(), model.len());
+        }
+        assert!(map.iter().map(|(&k, &v)| (k, v)).eq(model.iter().copied()));
+        assert_eq!(map.first(), model.first().map(|(k, v)| (k, v)));
+        assert_eq!(map.last(), model.last().map(|(k, v)| (k, v)));
+
+        map.sort_keys();
+        model.sort();
+        map.retain(|key, value| {
+            *value += 1;
+            key % 3 != 0
+        });
+        model.retain(|&(key, _)| key % 3 != 0);
+        assert!(map.clone().into_iter().eq(model.iter().map(|&(k, v)| (k, v + 1))));
+        for (index, (key, _)) in model.iter().enumerate() {
+            assert_eq!(map.get_full(key).map(|(i, _, _)| i), Some(index));
+        }
+        assert_eq!(map.pop().map(|(k, _)| k), model.pop().map(|(k, _)| k));
+        assert_eq!(map.shift_remove_index(0).map(|(k, _)| k), Some(model.remove(0).0));
+    }
+
+    #[test]
+    fn test_index_set_keeps_insertion_order() {
+        use crate::sets::CroIndexSet;
+
+        let mut set: CroIndexSet<&str> = ["b", "a", "c", "a"].into_iter().collect();
+        assert_eq!(set.len(), 3);
+        assert!(set.iter().copied().eq(["b", "a", "c"]));
+        assert_eq!(set.insert_full("a"), (1, false));
+        assert_eq!(set.insert_full("d"), (3, true));
+        assert_eq!(set.get_index_of("c"), Some(2));
+        assert_eq!(set.get_index(0), Some(&"b"));
+
+        set.move_index(3, 0);
+        assert!(set.iter().copied().eq(["d", "b", "a", "c"]));
+        assert!(set.swap_remove("d"));
+        assert!(set.iter().copied().eq(["c", "b", "a"]));
+        assert_eq!(set.shift_take("c"), Some("c"));
+        assert!(set.iter().copied().eq(["b", "a"]));
+        set.sort();
+        assert_eq!((set.first(), set.last()), (Some(&"a"), Some(&"b")));
+        assert_eq!(format!("{:?}", set), r#"{"a", "b"}"#);
+
+        let reversed: CroIndexSet<&str> = ["b", "a"].into_iter().collect();
+        assert_eq!(set, reversed);
+        assert!(set.iter().rev().copied().eq(["b", "a"]));
+    }
+
+    #[test]
+    fn test_collection_traits() {
+        use crate::maps::CroIndexMap;
+        use crate::sets::CroIndexSet;
+        use crate::traits::{Collection, MapEntry, MapLike, OrderedMapLike, Sequence, SetLike};
+
+        fn word_counts<M: MapLike<&'static str, usize>>(mut map: M) -> M {
+            for word in "the cat saw the dog and the bird".split(' ') {
+                *map.entry(word).or_default() += 1;
+            }
+            map.entry("cat").and_modify(|count| *count *= 10);
+            assert_eq!(map.insert("emu", 1), None);
+            assert_eq!(map.remove(&"dog"), Some(1));
+            if let Some(count) = map.get_mut(&"bird") {
+                *count += 1;
+            }
+            map
+        }
+
+        fn check_counts<M: MapLike<&'static str, usize>>(map: &M) {
+            assert_eq!(map.len(), 6);
+            assert_eq!(map.get(&"the"), Some(&3));
+            assert_eq!(map.get(&"cat"), Some(&10));
+            assert_eq!(map.get(&"bird"), Some(&2));
+            assert!(!map.contains_key(&"dog"));
+            assert_eq!(map.iter().map(|(_, count)| count).sum::<usize>(), 18);
+        }
+
+        check_counts(&word_counts(CroMap::new()));
+        check_counts(&word_counts(CroIndexMap::new()));
+        let mut tree = word_counts(CroBTree::new());
+        check_counts(&tree);
+
+        assert_eq!(OrderedMapLike::first_key_value(&tree), Some((&"and", &1)));
+        assert_eq!(OrderedMapLike::last_key_value(&tree), Some((&"the", &3)));
+        let middle: Vec<_> = OrderedMapLike::range(&tree, "b".."e").rev().map(|(k, _)| *k).collect();
+        assert_eq!(middle, ["cat", "bird"]);
+        assert_eq!(OrderedMapLike::pop_first(&mut tree), Some(("and", 1)));
+        Collection::wipe(&mut tree);
+        assert!(Collection::is_empty(&tree));
+
+        fn rotate<Q: Sequence<u32>>(mut seq: Q) -> Vec<u32> {
+            for value in 0..5 {
+                seq.push_back(value);
+            }
+            seq.push_front(9);
+            let back = seq.pop_back().unwrap();
+            seq.push_front(back);
+            let mut out = Vec::new();
+            while let Some(value) = seq.pop_front() {
+                out.push(value);
+            }
+            assert!(seq.is_empty());
+            out
+        }
+
+        let expected = [4, 9, 0, 1, 2, 3];
+        assert_eq!(rotate(CroVec::new()), expected);
+        assert_eq!(rotate(CroQue::new()), expected);
+        assert_eq!(rotate(CroLList::new()), expected);
+
+        fn dedup_len<T: SetLike<u32>>(mut set: T) -> usize {
+            for key in [3, 1, 3, 2, 1] {
+                set.insert(key);
+            }
+            assert!(set.contains(&2));
+            assert!(set.remove(&2));
+            assert!(!set.remove(&2));
+            assert_eq!(set.iter().count(), set.len());
+            set.len()
+        }
+
+        assert_eq!(dedup_len(CroHashSet::new()), 2);
+        assert_eq!(dedup_len(CroBTreeSet::new()), 2);
+        assert_eq!(dedup_len(CroIndexSet::new()), 2);
+    }
+
+    #[test]
+    fn test_prelude_and_std_compat() {
+        use crate::prelude::*;
+        use crate::std_compat::{
+            BTreeMap, BTreeSet, HashMap, HashSet, LinkedList, StdCapacity, StdCompat, Vec as StdVec,
+            VecDeque,
+        };
+
+        // Written as it would be against std, with only the imports changed.
+        let mut counts: HashMap<&str, usize> = HashMap::with_capacity(16);
+        let mut order: BTreeMap<usize, &str> = BTreeMap::new();
+        let mut seen: HashSet<&str> = HashSet::with_capacity(4);
+        let mut log: StdVec<&str> = StdVec::with_capacity(8);
+        for word in ["a", "b", "a", "c"] {
+            *counts.entry(word).or_insert(0) += 1;
+            if seen.insert(word) {
+                order.insert(order.len(), word);
+            }
+            log.push(word);
+        }
+        assert_eq!((counts.len(), seen.len(), order.len(), log.len()), (3, 3, 3, 4));
+        assert!(counts.capacity() >= 16 && log.capacity() >= 8);
+        assert_eq!(order.get(&2), Some(&"c"));
+        counts.clear();
+        order.clear();
+        seen.clear();
+        assert!(counts.is_empty() && order.is_empty() && seen.is_empty());
+
+        // The Cro spellings reach the containers that lacked them.
+        let tree: CroBTree<u32, u32> = CroBTree::new();
+        let set: CroBTreeSet<u32> = (0..3).collect();
+        let mut queue: VecDeque<u32> = VecDeque::with_capacity(2);
+        queue.push_back(1);
+        assert_eq!((tree.size(), set.size(), queue.size()), (0, 3, 1));
+        let mut list: CroLList<u32> = CroLList::new();
+        list.push_back(7);
+        assert_eq!(list.len(), 1);
+        list.clear();
+        assert!(list.is_empty());
+        // Like std, the list and B-tree set have len and clear but no capacity.
+        let mut links: LinkedList<u32> = LinkedList::new();
+        let mut keys: BTreeSet<u32> = BTreeSet::new();
+        links.push_back(1);
+        keys.insert(1);
+        assert_eq!((links.len(), keys.len()), (1, 1));
+        links.clear();
+        keys.clear();
+        assert!(links.is_empty() && keys.is_empty());
+        let index: CroIndexSet<u32> = StdCapacity::with_cap(4);
+        assert!(index.cap() >= 4 && index.capacity() == index.cap());
+
+        // Any node size ports, not just the default one.
+        let mut wide: BTreeMap<u32, u32, 16> = BTreeMap::default();
+        for key in 0..100 {
+            wide.insert(key, key);
+        }
+        assert_eq!((wide.size(), wide.get(&42)), (100, Some(&42)));
+    }
+
+    #[test]
+    fn test_std_conversions_round_trip() {
+        use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, LinkedList, VecDeque};
+
+        // Not Clone, so every conversion has to move.
+        #[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        struct Token(u32);
+
+        let vec: Vec<Token> = (0..10).map(Token).collect();
+        let ptr = vec.as_ptr();
+        let cro = CroVec::from(vec);
+        assert_eq!(cro.as_ptr(), ptr);
+        assert_eq!((cro.size(), cro.get(9)), (10, Some(&Token(9))));
+        let back = Vec::from(cro);
+        assert_eq!(back.as_ptr(), ptr);
+        assert_eq!(back, (0..10).map(Token).collect::<Vec<_>>());
+        assert!(Vec::from(CroVec::<Token>::new()).is_empty());
+
+        let mut deque: VecDeque<Token> = (3..6).map(Token).collect();
+        deque.push_front(Token(2));
+        let mut que = CroQue::from(deque);
+        assert_eq!(que.pop_front(), Some(Token(2)));
+        que.push_front(Token(1));
+        let deque = VecDeque::from(que);
+        assert!(deque.iter().map(|t| t.0).eq([1, 3, 4, 5]));
+
+        let list: LinkedList<Token> = (0..4).map(Token).collect();
+        let cro = CroLList::from(list);
+        assert_eq!(cro.size(), 4);
+        assert!(LinkedList::from(cro).into_iter().map(|t| t.0).eq(0..4));
+
+        let map: HashMap<Token, u32> = (0..100).map(|i| (Token(i), i * 2)).collect();
+        let cro = CroMap::from(map);
+        assert_eq!((cro.size(), cro.get(&Token(42))), (100, Some(&84)));
+        let map = HashMap::from(cro);
+        assert_eq!(map.len(), 100);
+        assert!((0..100).all(|i| map[&Token(i)] == i * 2));
+
+        let set: HashSet<Token> = (0..50).map(Token).collect();
+        let cro = CroHashSet::from(set);
+        assert!(cro.len() == 50 && cro.contains(&Token(49)));
+        assert_eq!(HashSet::from(cro), (0..50).map(Token).collect());
+
+        let map: BTreeMap<Token, u32> = (0..200).map(|i| (Token(i), i)).collect();
+        let tree = CroBTree::from(map);
+        assert_eq!(tree.first_key_value(), Some((&Token(0), &0)));
+        assert_eq!(tree.get(&Token(150)), Some(&150));
+        let map = BTreeMap::from(tree);
+        assert!(map.into_iter().map(|(k, v)| (k.0, v)).eq((0..200).map(|i| (i, i))));
+
+        let set: BTreeSet<Token> = (0..30).rev().map(Token).collect();
+        let cro = CroBTreeSet::from(set);
+        assert_eq!((cro.len(), cro.first()), (30, Some(&Token(0))));
+        assert!(BTreeSet::from(cro).into_iter().map(|t| t.0).eq(0..30));
+    }
+
+    #[test]
+    fn test_validate_containers() {
+        use crate::cmp::CroReverseOrder;
+        use crate::invariants::CroInvariantError;
+        use crate::maps::CroIndexMap;
+        use crate::sets::CroIndexSet;
+
+        let mut vec: CroVec<u32> = (0..20).collect();
+        vec.retain(|&x| x % 3 != 0);
+        vec.shrink_to_fit();
+        assert_eq!(vec.validate(), Ok(()));
+        // Corrupt the size by hand; the check has to notice.
+        let size = vec.size;
+        vec.size = vec.cap() + 1;
+        let err = vec.validate().unwrap_err();
+        vec.size = size;
+        assert_eq!(err, CroInvariantError::SizeExceedsCap { size: vec.cap() + 1, cap: vec.cap() });
+        assert!(err.to_string().contains("exceeds capacity"));
+        assert_eq!(vec.validate(), Ok(()));
+
+        let mut que = CroQue::new();
+        let mut list = CroLList::new();
+        for i in 0..10 {
+            que.push_back(i);
+            que.push_front(i);
+            list.push_back(i);
+            list.push_front(i);
+        }
+        que.pop_front();
+        list.pop_back();
+        assert_eq!((que.validate(), list.validate()), (Ok(()), Ok(())));
+        que.wipe();
+        list.wipe();
+        assert_eq!((que.validate(), list.validate()), (Ok(()), Ok(())));
+
+        // Caught partway through an incremental rehash, with tombstones in
+        // both tables.
+        let mut map = CroMap::new();
+        map.set_incremental_rehash(true);
+        let mut i = 0;
+        while map.rehash_progress().is_none() {
+            map.insert(i, i);
+            if i % 4 == 0 {
+                map.remove(&(i / 2));
+            }
+            i += 1;
+        }
+        map.remove(&1);
+        assert!(map.rehash_progress().is_some());
+        assert_eq!(map.validate(), Ok(()));
+
+        let mut index: CroIndexMap<u32, u32> = (0..100).map(|i| (i, i)).collect();
+        index.swap_remove(&10);
+        index.shift_remove(&20);
+        index.move_index(0, 50);
+        index.sort_by(|k1, _, k2, _| k2.cmp(k1));
+        assert_eq!(index.validate(), Ok(()));
+
+        let mut tree = CroBTree::with_order_and_comparator(4, CroReverseOrder);
+        for i in 0..500 {
+            tree.insert(i * 7 % 500, i);
+        }
+        tree.retain(|k, _| k % 5 != 0);
+        assert_eq!(tree.validate(), Ok(()));
+
+        let hash_set: CroHashSet<u32> = (0..50).collect();
+        let index_set: CroIndexSet<u32> = (0..50).collect();
+        let tree_set: CroBTreeSet<u32> = (0..50).collect();
+        assert_eq!(hash_set.validate(), Ok(()));
+        assert_eq!(index_set.validate(), Ok(()));
+        assert_eq!(tree_set.validate(), Ok(()));
+    }
+
     //btreemap
     fn create_test_tree() -> CroBTree<i32, &'static str> {
         let mut tree = CroBTree::new();
@@ -708,6 +1544,935 @@ mod tests {
         assert_eq!(tree.get(&0), Some(&"zero"));
     }
 
+    #[test]
+    fn test_btree_non_clone_values() {
+        struct Handle(u32);
+
+        let mut tree: CroBTree<u32, Handle> = CroBTree::new();
+        for i in 0..50 {
+            tree.insert(i, Handle(i * 2));
+        }
+        assert_eq!(tree.get(&7).map(|h| h.0), Some(14));
+        assert_eq!(tree.remove(&7).map(|h| h.0), Some(14));
+        assert_eq!(tree.get(&7).map(|h| h.0), None);
+        assert_eq!(tree.len(), 49);
+
+        let drained: Vec<u32> = tree.into_iter().map(|(k, _)| k).collect();
+        assert_eq!(drained.len(), 49);
+        assert!(drained.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    fn check_btree_against_std<const B: usize>(mut tree: CroBTree<u64, u64, B>, mut seed: u64) {
+        use std::collections::BTreeMap;
+
+        let mut model = BTreeMap::new();
+        for _ in 0..2000 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let key = (seed >> 33) % 200;
+            if (seed >> 20).is_multiple_of(3) {
+                assert_eq!(tree.remove(&key), model.remove(&key));
+            } else {
+                assert_eq!(tree.insert(key, key * 10), model.insert(key, key * 10));
+            }
+            assert_eq!(tree.len(), model.len());
+        }
+        tree.validate().unwrap();
+        let items: Vec<(u64, u64)> = tree.iter().map(|(k, v)| (*k, *v)).collect();
+        let expected: Vec<(u64, u64)> = model.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(items, expected);
+        let reversed: Vec<(u64, u64)> = tree.iter().rev().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(reversed, model.into_iter().rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_btree_remove_matches_std() {
+        for order in [3, 4, 5, 6, 7, 16, 33] {
+            check_btree_against_std(CroBTree::with_order(order), 0x2545_f491 + order as u64);
+        }
+        check_btree_against_std(CroBTree::<u64, u64, 8>::default(), 0x2545_f499);
+        check_btree_against_std(CroBTree::<u64, u64, 16>::default(), 0x2545_f4a1);
+    }
+
+    #[test]
+    fn test_btree_order_above_default() {
+        let mut tree = CroBTree::with_order(16);
+        for i in (0..1000).rev() {
+            tree.insert(i, i * 2);
+        }
+        tree.validate().unwrap();
+        assert_eq!(tree.len(), 1000);
+        assert_eq!(tree.get(&500), Some(&1000));
+        for i in (0..1000).step_by(3) {
+            assert_eq!(tree.remove(&i), Some(i * 2));
+        }
+        tree.validate().unwrap();
+        assert!(tree.iter().map(|(k, _)| *k).eq((0..1000).filter(|k| k % 3 != 0)));
+
+        let right = tree.split_off(&700);
+        assert_eq!(right.first_key_value(), Some((&700, &1400)));
+        right.validate().unwrap();
+
+        let mut set = CroBTreeSet::with_order(16);
+        set.extend(0..200);
+        set.validate().unwrap();
+        assert_eq!(set.len(), 200);
+    }
+
+    #[test]
+    fn test_btree_entry_counts_words() {
+        let mut counts: CroBTree<&str, usize> = CroBTree::with_order(3);
+        let text = "a b c a b a d e f g a h i j b";
+        for word in text.split(' ') {
+            counts.entry(word).and_modify(|n| *n += 1).or_insert(1);
+        }
+        assert_eq!(counts.len(), 10);
+        assert_eq!(counts.get(&"a"), Some(&4));
+        assert_eq!(counts.get(&"b"), Some(&3));
+        assert_eq!(counts.get(&"j"), Some(&1));
+
+        *counts.entry("z").or_default() += 7;
+        assert_eq!(counts.get(&"z"), Some(&7));
+        assert_eq!(*counts.entry("y").or_insert_with(|| 2), 2);
+        assert_eq!(counts.len(), 12);
+
+        let keys: Vec<&str> = counts.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec!["a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "y", "z"]);
+    }
+
+    #[test]
+    fn test_btree_entry_occupied_and_vacant() {
+        let mut tree = CroBTree::new();
+        match tree.entry(5) {
+            CroBTreeEntry::Vacant(entry) => {
+                assert_eq!(entry.key(), &5);
+                *entry.insert("five") = "FIVE";
+            }
+            CroBTreeEntry::Occupied(_) => panic!("expected a vacant entry"),
+        }
+        assert_eq!(tree.get(&5), Some(&"FIVE"));
+
+        for i in 0..50 {
+            let occupied = tree.entry(i).insert("x");
+            assert_eq!(occupied.key(), &i);
+        }
+        match tree.entry(20) {
+            CroBTreeEntry::Occupied(mut entry) => {
+                assert_eq!(entry.insert("twenty"), "x");
+                assert_eq!(entry.get(), &"twenty");
+                assert_eq!(entry.remove_entry(), (20, "twenty"));
+            }
+            CroBTreeEntry::Vacant(_) => panic!("expected an occupied entry"),
+        }
+        assert_eq!(tree.len(), 49);
+        assert!(!tree.contains_key(&20));
+        assert!(tree.iter().map(|(k, _)| *k).eq((0..50).filter(|&k| k != 20)));
+    }
+
+    #[test]
+    fn test_btree_first_last_pop() {
+        let mut tree = CroBTree::with_order(3);
+        assert_eq!(tree.first_key_value(), None);
+        assert_eq!(tree.pop_last(), None);
+        for i in (0..40).rev() {
+            tree.insert(i, i * 2);
+        }
+        assert_eq!(tree.first_key_value(), Some((&0, &0)));
+        assert_eq!(tree.last_key_value(), Some((&39, &78)));
+        assert_eq!(tree.pop_first(), Some((0, 0)));
+        assert_eq!(tree.pop_last(), Some((39, 78)));
+        assert_eq!(tree.first_key_value(), Some((&1, &2)));
+        assert_eq!(tree.last_key_value(), Some((&38, &76)));
+        assert_eq!(tree.len(), 38);
+    }
+
+    #[test]
+    fn test_btree_cursors() {
+        use std::ops::Bound;
+
+        let mut events = CroBTree::with_order(4);
+        for ts in (0..100).step_by(10) {
+            events.insert(ts, format!("event-{}", ts));
+        }
+
+        let mut cursor = events.lower_bound(Bound::Included(&35));
+        assert_eq!(cursor.peek_prev().map(|(k, _)| *k), Some(30));
+        assert_eq!(cursor.peek_next().map(|(k, _)| *k), Some(40));
+        assert_eq!(cursor.next().map(|(k, _)| *k), Some(40));
+        assert_eq!(cursor.next().map(|(k, _)| *k), Some(50));
+        assert_eq!(cursor.prev().map(|(k, _)| *k), Some(50));
+        assert_eq!(cursor.prev().map(|(k, _)| *k), Some(40));
+
+        let cursor = events.lower_bound(Bound::Included(&40));
+        assert_eq!(cursor.peek_next().map(|(k, _)| *k), Some(40));
+        let cursor = events.lower_bound(Bound::Excluded(&40));
+        assert_eq!(cursor.peek_next().map(|(k, _)| *k), Some(50));
+        let cursor = events.upper_bound(Bound::Included(&40));
+        assert_eq!(cursor.peek_prev().map(|(k, _)| *k), Some(40));
+        let cursor = events.upper_bound(Bound::Excluded(&40));
+        assert_eq!(cursor.peek_prev().map(|(k, _)| *k), Some(30));
+
+        let mut cursor = events.lower_bound(Bound::Unbounded);
+        assert_eq!(cursor.prev(), None);
+        assert_eq!(cursor.next().map(|(k, _)| *k), Some(0));
+        let mut cursor = events.upper_bound(Bound::Unbounded);
+        assert_eq!(cursor.next(), None);
+        assert_eq!(cursor.prev().map(|(k, _)| *k), Some(90));
+
+        let mut cursor = events.lower_bound_mut(Bound::Included(&20));
+        if let Some((_, value)) = cursor.peek_next() {
+            value.push_str("-seen");
+        }
+        assert_eq!(cursor.remove_prev(), Some((10, "event-10".to_string())));
+        assert_eq!(cursor.remove_next(), Some((20, "event-20-seen".to_string())));
+        assert_eq!(cursor.as_cursor().peek_prev().map(|(k, _)| *k), Some(0));
+        assert_eq!(cursor.next().map(|(k, _)| *k), Some(30));
+        let keys: Vec<i32> = events.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![0, 30, 40, 50, 60, 70, 80, 90]);
+
+        let empty: CroBTree<i32, ()> = CroBTree::new();
+        assert_eq!(empty.lower_bound(Bound::Included(&1)).peek_next(), None);
+    }
+
+    #[test]
+    fn test_btree_range_matches_std() {
+        use std::collections::BTreeMap;
+        use std::ops::Bound;
+
+        let mut tree = CroBTree::with_order(3);
+        let mut model = BTreeMap::new();
+        for i in 0..300u32 {
+            let key = i.wrapping_mul(7919) % 500;
+            tree.insert(key, i);
+            model.insert(key, i);
+        }
+        let bounds = |x: u32| [Bound::Included(x), Bound::Excluded(x), Bound::Unbounded];
+        for lo in (0..520).step_by(37) {
+            for hi in (lo..520).step_by(41) {
+                for start in bounds(lo) {
+                    for end in bounds(hi) {
+                        if lo == hi && matches!((start, end), (Bound::Excluded(_), Bound::Excluded(_))) {
+                            continue;
+                        }
+                        let got: Vec<_> = tree.range((start, end)).collect();
+                        let expected: Vec<_> = model.range((start, end)).collect();
+                        assert_eq!(got, expected);
+                        let got: Vec<_> = tree.range((start, end)).rev().collect();
+                        let expected: Vec<_> = model.range((start, end)).rev().collect();
+                        assert_eq!(got, expected);
+                    }
+                }
+            }
+        }
+
+        for (_, value) in tree.range_mut(100..200) {
+            *value = 0;
+        }
+        assert!(tree.range(100..200).all(|(_, v)| *v == 0));
+        assert!(tree.range(..100).eq(model.range(..100)));
+    }
+
+    #[test]
+    #[should_panic(expected = "range start is greater than range end")]
+    fn test_btree_range_inverted() {
+        use std::ops::Bound;
+
+        let tree: CroBTree<i32, ()> = CroBTree::new();
+        tree.range((Bound::Included(5), Bound::Excluded(2)));
+    }
+
+    #[test]
+    fn test_btree_from_sorted_iter() {
+        for n in [0usize, 1, 2, 5, 6, 7, 31, 100, 1000] {
+            let tree: CroBTree<_, _> = CroBTree::from_sorted_iter((0..n).map(|i| (i, i * 3)));
+            tree.validate().unwrap();
+            assert_eq!(tree.len(), n);
+            assert!(tree.iter().map(|(k, v)| (*k, *v)).eq((0..n).map(|i| (i, i * 3))));
+            for fill in 2..=5 {
+                let mut tree: CroBTree<_, _> = CroBTree::from_sorted_iter_with_fill((0..n).map(|i| (i, ())), fill);
+                tree.validate().unwrap();
+                assert_eq!(tree.len(), n);
+                tree.insert(n, ());
+                tree.validate().unwrap();
+            }
+        }
+
+        let tree: CroBTree<_, _> = CroBTree::from_sorted_iter([(1, 'a'), (2, 'b'), (2, 'c'), (3, 'd')]);
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.get(&2), Some(&'c'));
+    }
+
+    #[test]
+    #[should_panic(expected = "requires keys in ascending order")]
+    fn test_btree_from_sorted_iter_unsorted() {
+        CroBTree::<_, _>::from_sorted_iter([(2, ()), (1, ())]);
+    }
+
+    #[test]
+    fn test_btree_from_sorted_iter_custom() {
+        use crate::cmp::CroReverseOrder;
+
+        let tree: CroBTree<u64, u64, 4, Stats, CroReverseOrder> =
+            CroBTree::from_sorted_iter((0..200).rev().map(|k| (k, k * 2)));
+        tree.validate().unwrap();
+        assert_eq!(tree.len(), 200);
+        assert_eq!(tree.first_key_value(), Some((&199, &398)));
+        assert_eq!(tree.aggregate(..).sum, (0..200).map(|k| k * 2).sum::<u64>());
+
+        let set: CroBTreeSet<u64, CroReverseOrder> = CroBTreeSet::from_sorted_iter((0..50).rev());
+        assert!(set.iter().copied().eq((0..50).rev()));
+    }
+
+    #[test]
+    #[should_panic(expected = "requires keys in ascending order")]
+    fn test_btree_from_sorted_iter_custom_unsorted() {
+        use crate::cmp::CroReverseOrder;
+
+        CroBTree::<u64, (), 6, (), CroReverseOrder>::from_sorted_iter([(1, ()), (2, ())]);
+    }
+
+    #[test]
+    fn test_btree_split_off_matches_std() {
+        use std::collections::BTreeMap;
+
+        for order in 3..=6 {
+            for n in [0u32, 1, 4, 17, 200] {
+                for at in (0..n + 2).step_by(3) {
+                    let mut tree = CroBTree::with_order(order);
+                    let mut model = BTreeMap::new();
+                    for i in 0..n {
+                        let key = (i * 37) % (n + 1);
+                        tree.insert(key, i);
+                        model.insert(key, i);
+                    }
+                    let right = tree.split_off(&at);
+                    let model_right = model.split_off(&at);
+                    tree.validate().unwrap();
+                    right.validate().unwrap();
+                    assert!(tree.iter().eq(model.iter()));
+                    assert!(right.iter().eq(model_right.iter()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_btree_drain_range_matches_std() {
+        use std::collections::BTreeMap;
+        use std::ops::Bound;
+
+        let mut seed: u64 = 0x51ed_270b;
+        for order in 3..=6 {
+            let mut tree = CroBTree::with_order(order);
+            let mut model = BTreeMap::new();
+            for _ in 0..300 {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let key = (seed >> 33) % 400;
+                tree.insert(key, seed);
+                model.insert(key, seed);
+                if (seed >> 24).is_multiple_of(4) {
+                    let lo = (seed >> 40) % 400;
+                    let hi = lo + (seed >> 50) % 60;
+                    let start = if seed & 1 == 0 { Bound::Included(lo) } else { Bound::Excluded(lo) };
+                    let end = if seed & 2 == 0 { Bound::Included(hi) } else { Bound::Unbounded };
+                    let drained: Vec<(u64, u64)> = tree.drain_range((start, end)).collect();
+                    let expected: Vec<u64> = model.range((start, end)).map(|(k, _)| *k).collect();
+                    for key in &expected {
+                        model.remove(key);
+                    }
+                    assert_eq!(drained.iter().map(|(k, _)| *k).collect::<Vec<_>>(), expected);
+                    tree.validate().unwrap();
+                    assert!(tree.iter().eq(model.iter()));
+                }
+            }
+            assert_eq!(tree.remove_range(..), model.len());
+            assert!(tree.is_empty());
+            tree.validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_btree_append() {
+        let mut left = CroBTree::with_order(4);
+        let mut right = CroBTree::with_order(4);
+        for i in 0..100 {
+            left.insert(i * 2, "left");
+            right.insert(i * 3, "right");
+        }
+        left.append(&mut right);
+        left.validate().unwrap();
+        assert!(right.is_empty());
+        assert_eq!(left.len(), 100 + 100 - 34);
+        assert_eq!(left.get(&6), Some(&"right"));
+        assert_eq!(left.get(&4), Some(&"left"));
+
+        let mut empty = CroBTree::with_order(4);
+        empty.append(&mut left);
+        empty.validate().unwrap();
+        assert_eq!(empty.len(), 166);
+    }
+
+    #[test]
+    fn test_btree_into_iter_partial_drop() {
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let mut tree = CroBTree::new();
+        for i in 0..100 {
+            tree.insert(i, Rc::clone(&counter));
+        }
+        let mut iter = tree.into_iter();
+        assert_eq!(iter.next().map(|(k, _)| k), Some(0));
+        assert_eq!(iter.next_back().map(|(k, _)| k), Some(99));
+        assert_eq!(iter.len(), 98);
+        drop(iter);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_btree_set_non_clone_keys() {
+        #[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+        struct Token(u32);
+
+        let mut set = CroBTreeSet::new();
+        for i in 0..20 {
+            set.insert(Token(i));
+        }
+        assert_eq!(set.take(&Token(3)), Some(Token(3)));
+        assert_eq!(set.pop_first(), Some(Token(0)));
+        assert_eq!(set.pop_last(), Some(Token(19)));
+
+        set.retain(|t| t.0 % 2 == 0);
+        let mut right = set.split_off(&Token(10));
+        assert_eq!(set.iter().map(|t| t.0).collect::<Vec<_>>(), vec![2, 4, 6, 8]);
+        assert_eq!(right.iter().map(|t| t.0).collect::<Vec<_>>(), vec![10, 12, 14, 16, 18]);
+
+        set.append(&mut right);
+        assert!(right.is_empty());
+        assert_eq!(set.len(), 9);
+        assert_eq!(set.into_iter().next_back(), Some(Token(18)));
+    }
+
+    #[test]
+    fn test_btree_borrowed_lookups() {
+        use std::ops::Bound;
+
+        let mut tree: CroBTree<String, usize> = CroBTree::with_order(3);
+        for (i, name) in ["ash", "birch", "cedar", "elm", "fir", "oak", "pine"].iter().enumerate() {
+            tree.insert(name.to_string(), i);
+        }
+        assert_eq!(tree.get("cedar"), Some(&2));
+        assert!(tree.contains_key("oak"));
+        assert!(!tree.contains_key("yew"));
+        *tree.get_mut("elm").unwrap() += 10;
+        assert_eq!(tree.get_key_value("elm"), Some((&"elm".to_string(), &13)));
+        assert_eq!(tree.remove("fir"), Some(4));
+        assert_eq!(tree.remove_entry("ash"), Some(("ash".to_string(), 0)));
+
+        let names: Vec<&str> = tree
+            .range::<str, _>((Bound::Included("b"), Bound::Excluded("f")))
+            .map(|(k, _)| k.as_str())
+            .collect();
+        assert_eq!(names, vec!["birch", "cedar", "elm"]);
+        let cursor = tree.lower_bound(Bound::Excluded("cedar"));
+        assert_eq!(cursor.peek_next().map(|(k, _)| k.as_str()), Some("elm"));
+
+        let right = tree.split_off("d");
+        assert_eq!(tree.len(), 2);
+        assert_eq!(right.len(), 3);
+
+        let mut set: CroBTreeSet<String> = ["x", "y", "z"].iter().map(|s| s.to_string()).collect();
+        assert!(set.contains("y"));
+        assert_eq!(set.get("z").map(String::as_str), Some("z"));
+        assert_eq!(set.take("x"), Some("x".to_string()));
+        assert!(set.remove("y"));
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_btree_rank_select_matches_sorted() {
+        for order in 3..=6 {
+            let mut tree = CroBTree::with_order(order);
+            let mut sorted = Vec::new();
+            let mut seed: u64 = 0x2545_f491_4f6c_dd1d + order as u64;
+            for step in 0..1500 {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let key = (seed >> 33) % 400;
+                if step % 3 == 2 {
+                    tree.remove(&key);
+                    sorted.retain(|k| *k != key);
+                } else if tree.insert(key, key * 2).is_none() {
+                    let pos = sorted.partition_point(|k| *k < key);
+                    sorted.insert(pos, key);
+                }
+            }
+            tree.validate().unwrap();
+
+            for key in 0..=400 {
+                assert_eq!(tree.rank(&key), sorted.partition_point(|k| *k < key));
+            }
+            for (index, key) in sorted.iter().enumerate() {
+                assert_eq!(tree.select(index), Some((key, &(key * 2))));
+            }
+            assert_eq!(tree.get_index(sorted.len()), None);
+
+            let (a, b) = (sorted.len() / 4, sorted.len() * 3 / 4);
+            let keys: Vec<u64> = tree.range_by_index(a..b).map(|(k, _)| *k).collect();
+            assert_eq!(keys, sorted[a..b]);
+            let keys: Vec<u64> = tree.range_by_index(b..).rev().map(|(k, _)| *k).collect();
+            assert!(keys.iter().rev().eq(sorted[b..].iter()));
+            assert_eq!(tree.range_by_index(a..=a).count(), 1);
+            assert_eq!(tree.range_by_index(a..a).count(), 0);
+
+            let right = tree.split_off(&sorted[a]);
+            assert_eq!(tree.len(), a);
+            assert_eq!(right.len(), sorted.len() - a);
+            tree.validate().unwrap();
+            right.validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_btree_get_index_mut_and_set_position() {
+        let mut tree: CroBTree<_, _> = CroBTree::from_sorted_iter((0..50).map(|k| (k * 2, 0)));
+        *tree.get_index_mut(10).unwrap().1 = 7;
+        assert_eq!(tree.get(&20), Some(&7));
+        assert_eq!(tree.range_by_index(..).count(), 50);
+
+        let set: CroBTreeSet<i32> = (0..50).map(|k| k * 3).collect();
+        assert_eq!(set.nth(4), Some(&12));
+        assert_eq!(set.nth(50), None);
+        assert_eq!(set.position(&12), Some(4));
+        assert_eq!(set.position(&13), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_btree_range_by_index_out_of_bounds() {
+        let tree: CroBTree<_, _> = CroBTree::from_sorted_iter((0..5).map(|k| (k, k)));
+        tree.range_by_index(2..6);
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Stats {
+        count: usize,
+        sum: u64,
+        max: Option<u64>,
+        first: Option<u64>,
+        last: Option<u64>,
+    }
+
+    impl crate::maps::Summary<u64, u64> for Stats {
+        fn identity() -> Self {
+            Stats { count: 0, sum: 0, max: None, first: None, last: None }
+        }
+
+        fn from_entry(key: &u64, val: &u64) -> Self {
+            Stats { count: 1, sum: *val, max: Some(*val), first: Some(*key), last: Some(*key) }
+        }
+
+        fn combine(&self, other: &Self) -> Self {
+            Stats {
+                count: self.count + other.count,
+                sum: self.sum + other.sum,
+                max: self.max.max(other.max),
+                first: self.first.or(other.first),
+                last: other.last.or(self.last),
+            }
+        }
+    }
+
+    fn stats_of<'a>(entries: impl Iterator<Item = (&'a u64, &'a u64)>) -> Stats {
+        use crate::maps::Summary;
+        entries.fold(Stats::identity(), |acc, (k, v)| acc.combine(&Stats::from_entry(k, v)))
+    }
+
+    #[test]
+    fn test_btree_aggregate_matches_std() {
+        use std::collections::BTreeMap;
+
+        for order in 3..=6 {
+            let mut tree: CroBTree<u64, u64, 6, Stats> = CroBTree::with_summary_and_order(order);
+            let mut model = BTreeMap::new();
+            let mut seed: u64 = 0x853c_49e6_748f_ea9b + order as u64;
+            let mut next = || {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                seed >> 33
+            };
+            for step in 0..2000 {
+                let key = next() % 500;
+                match step % 10 {
+                    0..=4 => {
+                        tree.insert(key, key);
+                        model.insert(key, key);
+                    }
+                    5 | 6 => {
+                        assert_eq!(tree.remove(&key), model.remove(&key));
+                    }
+                    7 => {
+                        if let Some(val) = tree.get_mut(&key) {
+                            *val += 3;
+                            *model.get_mut(&key).unwrap() += 3;
+                        }
+                    }
+                    8 => {
+                        for (_, val) in tree.range_mut(key..key + 20) {
+                            *val += 1;
+                        }
+                        for (_, val) in model.range_mut(key..key + 20) {
+                            *val += 1;
+                        }
+                    }
+                    _ => {
+                        let mut right = tree.split_off(&key);
+                        let mut model_right = model.split_off(&key);
+                        let hi = key + next() % 50;
+                        tree.remove_range(..hi.saturating_sub(100));
+                        model.retain(|k, _| *k >= hi.saturating_sub(100));
+                        assert_eq!(right.aggregate(..), stats_of(model_right.iter()));
+                        tree.append(&mut right);
+                        model.append(&mut model_right);
+                    }
+                }
+                let lo = next() % 520;
+                let hi = lo + next() % 200;
+                assert_eq!(tree.aggregate(lo..hi), stats_of(model.range(lo..hi)));
+                assert_eq!(tree.aggregate(lo..=hi), stats_of(model.range(lo..=hi)));
+                assert_eq!(tree.aggregate(..hi), stats_of(model.range(..hi)));
+                assert_eq!(tree.aggregate(lo..), stats_of(model.range(lo..)));
+                let narrow = lo + next() % 16;
+                assert_eq!(tree.aggregate(lo..narrow), stats_of(model.range(lo..narrow)));
+            }
+            assert_eq!(tree.aggregate(..), stats_of(model.iter()));
+            tree.validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_btree_aggregate_entry_updates() {
+        let mut tree: CroBTree<u64, u64, 6, Stats> = CroBTree::with_summary();
+        for key in 0..200 {
+            *tree.entry(key % 50).or_insert(0) += key;
+        }
+        let total: u64 = (0..200).sum();
+        assert_eq!(tree.aggregate(..).sum, total);
+        assert_eq!(tree.aggregate(10..20).count, 10);
+        if let CroBTreeEntry::Occupied(mut entry) = tree.entry(0) {
+            entry.insert(1000);
+        }
+        assert_eq!(tree.aggregate(..=0).max, Some(1000));
+        assert_eq!(tree.aggregate(60..70), Stats { count: 0, sum: 0, max: None, first: None, last: None });
+    }
+
+    thread_local! {
+        static SUMMARIZED: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Total(u64);
+
+    impl crate::maps::Summary<u64, u64> for Total {
+        fn identity() -> Self {
+            Total(0)
+        }
+
+        fn from_entry(_key: &u64, val: &u64) -> Self {
+            SUMMARIZED.with(|count| count.set(count.get() + 1));
+            Total(*val)
+        }
+
+        fn combine(&self, other: &Self) -> Self {
+            Total(self.0 + other.0)
+        }
+    }
+
+    #[test]
+    fn test_btree_summaries_stay_current() {
+        use crate::cmp::CroReverseOrder;
+
+        let summarized = || SUMMARIZED.with(|count| count.replace(0));
+        let mut tree: CroBTree<u64, u64, 8, Total, CroReverseOrder> = CroBTree::with_summary();
+        for key in 0..2000 {
+            tree.insert(key, key);
+        }
+        for key in (0..2000).step_by(3) {
+            tree.remove(&key);
+        }
+        tree.insert(5, 100);
+        let mut right = tree.split_off(&1000);
+        tree.append(&mut right);
+        let total: u64 = tree.iter().map(|(_, val)| val).sum();
+
+        // The caches are already current, so only the border paths of the
+        // range get folded entry by entry.
+        summarized();
+        assert_eq!(tree.aggregate(..), Total(total));
+        let warm = summarized();
+        assert!(warm < tree.len() / 4);
+
+        // `&mut V` leaves the path above it stale until the next query.
+        *tree.get_mut(&1000).unwrap() += 1;
+        assert_eq!(tree.aggregate(..), Total(total + 1));
+        assert!(summarized() < warm + tree.len() / 8);
+        assert_eq!(tree.aggregate(..), Total(total + 1));
+        assert_eq!(summarized(), warm);
+    }
+
+    #[test]
+    fn test_btree_reverse_comparator_matches_std() {
+        use crate::cmp::CroReverseOrder;
+        use std::cmp::Reverse;
+        use std::collections::BTreeMap;
+        use std::ops::Bound;
+
+        let mut tree: CroBTree<_, _, 4, (), _> = CroBTree::with_summary_order_and_comparator(3, CroReverseOrder);
+        let mut model = BTreeMap::new();
+        let mut seed: u64 = 0x1234_5678_9abc_def0;
+        for step in 0..1500 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let key = (seed >> 33) % 300;
+            if step % 3 == 2 {
+                assert_eq!(tree.remove(&key), model.remove(&Reverse(key)));
+            } else {
+                assert_eq!(tree.insert(key, step), model.insert(Reverse(key), step));
+            }
+        }
+        tree.validate().unwrap();
+        assert!(tree.iter().map(|(k, v)| (*k, *v)).eq(model.iter().map(|(k, v)| (k.0, *v))));
+        assert_eq!(tree.first_key_value().map(|(k, _)| *k), model.keys().next().map(|k| k.0));
+
+        let bounds = (Bound::Included(200), Bound::Included(100));
+        let range: Vec<u64> = tree.range(bounds).map(|(k, _)| *k).collect();
+        let expected: Vec<u64> = model.range(Reverse(200)..=Reverse(100)).map(|(k, _)| k.0).collect();
+        assert_eq!(range, expected);
+
+        let mut right = tree.split_off(&150);
+        assert!(tree.iter().all(|(k, _)| *k > 150));
+        assert!(right.iter().all(|(k, _)| *k <= 150));
+        tree.append(&mut right);
+        tree.validate().unwrap();
+        assert_eq!(tree.len(), model.len());
+    }
+
+    #[derive(Clone)]
+    struct CaseInsensitive;
+
+    impl crate::cmp::Comparator<str> for CaseInsensitive {
+        fn compare(&self, a: &str, b: &str) -> std::cmp::Ordering {
+            a.bytes().map(|b| b.to_ascii_lowercase()).cmp(b.bytes().map(|b| b.to_ascii_lowercase()))
+        }
+    }
+
+    impl crate::cmp::Comparator<String> for CaseInsensitive {
+        fn compare(&self, a: &String, b: &String) -> std::cmp::Ordering {
+            crate::cmp::Comparator::<str>::compare(self, a, b)
+        }
+    }
+
+    #[test]
+    fn test_btree_custom_comparators() {
+        let mut set = CroBTreeSet::with_comparator(CaseInsensitive);
+        assert!(set.insert("Hello".to_string()));
+        assert!(!set.insert("HELLO".to_string()));
+        assert!(set.insert("apple".to_string()));
+        assert!(set.insert("Zebra".to_string()));
+        assert!(set.contains("hello"));
+        assert_eq!(set.get("ZEBRA").map(String::as_str), Some("Zebra"));
+        let names: Vec<&str> = set.iter().map(String::as_str).collect();
+        assert_eq!(names, vec!["apple", "Hello", "Zebra"]);
+
+        let mut other = CroBTreeSet::with_comparator(CaseInsensitive);
+        other.insert("APPLE".to_string());
+        other.insert("mango".to_string());
+        let union: Vec<&str> = set.union(&other).map(String::as_str).collect();
+        assert_eq!(union, vec!["apple", "Hello", "mango", "Zebra"]);
+        assert_eq!(set.intersection(&other).count(), 1);
+
+        let mut floats = CroBTree::with_comparator(|a: &f64, b: &f64| a.total_cmp(b));
+        for (i, x) in [2.5, -1.0, f64::INFINITY, 0.0, -0.0, 10.25].iter().enumerate() {
+            floats.insert(*x, i);
+        }
+        let keys: Vec<f64> = floats.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![-1.0, -0.0, 0.0, 2.5, 10.25, f64::INFINITY]);
+        assert_eq!(floats.get(&2.5), Some(&0));
+        assert_eq!(floats.rank(&1.0), 3);
+    }
+
+    #[test]
+    fn test_crovec_binary_search_with() {
+        use crate::cmp::CroReverseOrder;
+
+        let mut vec = CroVec::new();
+        for x in [9, 7, 5, 3, 1] {
+            vec.push(x);
+        }
+        assert_eq!(vec.binary_search_with(&5, &CroReverseOrder), Ok(2));
+        assert_eq!(vec.binary_search_with(&4, &CroReverseOrder), Err(3));
+        assert_eq!(vec.binary_search_by_key_with(&-7, |x| -x, &|a: &i32, b: &i32| a.cmp(b)), Ok(1));
+    }
+
+    fn check_size_hint<I: Iterator>(iter: I) {
+        let (lower, upper) = iter.size_hint();
+        let count = iter.count();
+        assert!(lower <= count, "lower bound {} above {}", lower, count);
+        assert!(upper.is_none_or(|upper| count <= upper), "upper bound {:?} below {}", upper, count);
+    }
+
+    #[test]
+    fn test_btree_set_algebra_matches_std() {
+        use std::collections::BTreeSet;
+
+        let mut seed: u64 = 0x0bad_5eed_1234_5678;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            seed >> 33
+        };
+        for (a_len, b_len, span) in [(0, 10, 50), (40, 50, 100), (5, 2000, 4000), (3000, 7, 500), (300, 300, 400)] {
+            let a_keys: Vec<u64> = (0..a_len).map(|_| next() % span).collect();
+            let b_keys: Vec<u64> = (0..b_len).map(|_| next() % span).collect();
+            let a: CroBTreeSet<u64> = a_keys.iter().copied().collect();
+            let b: CroBTreeSet<u64> = b_keys.iter().copied().collect();
+            let std_a: BTreeSet<u64> = a_keys.iter().copied().collect();
+            let std_b: BTreeSet<u64> = b_keys.iter().copied().collect();
+
+            assert!(a.union(&b).eq(std_a.union(&std_b)));
+            assert!(a.intersection(&b).eq(std_a.intersection(&std_b)));
+            assert!(b.intersection(&a).eq(std_b.intersection(&std_a)));
+            assert!(a.difference(&b).eq(std_a.difference(&std_b)));
+            assert!(b.difference(&a).eq(std_b.difference(&std_a)));
+            assert!(a.symmetric_difference(&b).eq(std_a.symmetric_difference(&std_b)));
+            assert_eq!(a.is_disjoint(&b), std_a.is_disjoint(&std_b));
+            assert_eq!(a.is_subset(&b), std_a.is_subset(&std_b));
+            assert_eq!(b.is_superset(&a), std_b.is_superset(&std_a));
+
+            check_size_hint(a.union(&b));
+            check_size_hint(a.intersection(&b));
+            check_size_hint(a.difference(&b));
+            check_size_hint(a.symmetric_difference(&b));
+        }
+
+        let small: CroBTreeSet<u64> = [3, 900, 1999].iter().copied().collect();
+        let large: CroBTreeSet<u64> = (0..2000).collect();
+        assert!(small.is_subset(&large));
+        assert!(!small.is_disjoint(&large));
+        let odd: CroBTreeSet<u64> = (0..1000).map(|x| x * 2 + 1).collect();
+        let even: CroBTreeSet<u64> = (0..10).map(|x| x * 2).collect();
+        assert!(even.is_disjoint(&odd));
+        assert_eq!(odd.iter().next_back(), Some(&1999));
+        assert_eq!(odd.range(10..20).next_back(), Some(&19));
+    }
+
+    #[test]
+    fn test_btree_set_mutation_matches_std() {
+        use std::collections::BTreeSet;
+
+        let mut seed: u64 = 0x1234_0038_dead_beef;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            seed >> 33
+        };
+        for order in [3, 4, 6] {
+            let mut set = CroBTreeSet::with_order(order);
+            let mut std_set = BTreeSet::new();
+            for step in 0..6000 {
+                let key = next() % 800;
+                match next() % 10 {
+                    0..=3 => assert_eq!(set.insert(key), std_set.insert(key)),
+                    4 => assert_eq!(set.remove(&key), std_set.remove(&key)),
+                    5 => assert_eq!(set.take(&key), std_set.take(&key)),
+                    6 => assert_eq!(set.replace(key), std_set.replace(key)),
+                    7 => assert_eq!(set.pop_first(), std_set.pop_first()),
+                    8 => assert_eq!(set.pop_last(), std_set.pop_last()),
+                    _ => {
+                        let modulus = next() % 5 + 2;
+                        set.retain(|k| k % modulus != key % modulus);
+                        std_set.retain(|k| k % modulus != key % modulus);
+                        set.extend((0..400).map(|_| next() % 800).inspect(|k| {
+                            std_set.insert(*k);
+                        }));
+                    }
+                }
+                if step % 500 == 0 {
+                    let mut tail = set.split_off(&key);
+                    let std_tail = std_set.split_off(&key);
+                    tail.validate().unwrap();
+                    assert!(tail.iter().eq(std_tail.iter()));
+                    set.append(&mut tail);
+                    std_set.extend(std_tail);
+                }
+                if step % 97 == 0 {
+                    set.validate().unwrap();
+                }
+                assert_eq!(set.len(), std_set.len());
+            }
+            set.validate().unwrap();
+            assert!(set.iter().eq(std_set.iter()));
+
+            set.retain(|_| false);
+            set.validate().unwrap();
+            assert!(set.is_empty());
+            assert!(set.insert(5));
+            assert_eq!(set.first(), Some(&5));
+        }
+    }
+
+    #[test]
+    fn test_set_k_way_merge_matches_std() {
+        use std::collections::BTreeSet;
+
+        let mut seed: u64 = 0x0039_5eed_4242_1717;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            seed >> 33
+        };
+        let shapes: [&[(u64, u64)]; 5] = [
+            &[],
+            &[(100, 300)],
+            &[(2000, 3000), (1500, 3000), (2500, 3000), (1800, 3000)],
+            &[(40, 200), (5000, 6000), (3000, 6000), (0, 10)],
+            &[(300, 400), (20, 400), (300, 400), (350, 400), (200, 400), (320, 400)],
+        ];
+        for shape in shapes {
+            let keys: Vec<Vec<u64>> = shape
+                .iter()
+                .map(|&(len, span)| (0..len).map(|_| next() % span).collect())
+                .collect();
+            let std_sets: Vec<BTreeSet<u64>> = keys.iter().map(|k| k.iter().copied().collect()).collect();
+            let std_union: BTreeSet<u64> = std_sets.iter().flatten().copied().collect();
+            let std_intersection: BTreeSet<u64> = match std_sets.split_first() {
+                Some((first, rest)) => first
+                    .iter()
+                    .filter(|key| rest.iter().all(|set| set.contains(key)))
+                    .copied()
+                    .collect(),
+                None => BTreeSet::new(),
+            };
+
+            let btree_sets: Vec<CroBTreeSet<u64>> = keys.iter().map(|k| k.iter().copied().collect()).collect();
+            let refs: Vec<&CroBTreeSet<u64>> = btree_sets.iter().collect();
+            assert!(CroBTreeSet::union_all(&refs).eq(std_union.iter()));
+            assert!(CroBTreeSet::intersect_all(&refs).eq(std_intersection.iter()));
+            check_size_hint(CroBTreeSet::union_all(&refs));
+            check_size_hint(CroBTreeSet::intersect_all(&refs));
+            let mut union = CroBTreeSet::union_all(&refs);
+            for rest in (0..std_union.len()).rev() {
+                union.next();
+                let (lower, upper) = union.size_hint();
+                assert!(lower <= rest && upper.is_none_or(|upper| rest <= upper));
+            }
+
+            let hash_sets: Vec<CroHashSet<u64>> = keys.iter().map(|k| k.iter().copied().collect()).collect();
+            let refs: Vec<&CroHashSet<u64>> = hash_sets.iter().collect();
+            let mut union: Vec<u64> = CroHashSet::union_all(&refs).copied().collect();
+            union.sort_unstable();
+            assert!(union.iter().eq(std_union.iter()));
+            let mut intersection: Vec<u64> = CroHashSet::intersect_all(&refs).copied().collect();
+            intersection.sort_unstable();
+            assert!(intersection.iter().eq(std_intersection.iter()));
+            check_size_hint(CroHashSet::union_all(&refs));
+            check_size_hint(CroHashSet::intersect_all(&refs));
+        }
+    }
+
     #[test]
     fn test_btree_set_insert_contains() {
         let mut set = CroBTreeSet::new();
@@ -820,6 +2585,139 @@ mod tests {
         assert_eq!(sym, vec![1, 2, 4]);
     }
 
+    #[test]
+    fn test_hash_set_algebra_matches_std() {
+        use std::collections::HashSet;
+
+        let sorted = |iter: &mut dyn Iterator<Item = &u64>| {
+            let mut keys: Vec<u64> = iter.copied().collect();
+            keys.sort_unstable();
+            keys
+        };
+        let mut seed: u64 = 0x5eed_cafe_f00d_0001;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            seed >> 33
+        };
+        for (a_len, b_len, span) in [(0, 10, 50), (40, 50, 100), (5, 2000, 4000), (3000, 7, 500)] {
+            let a_keys: Vec<u64> = (0..a_len).map(|_| next() % span).collect();
+            let b_keys: Vec<u64> = (0..b_len).map(|_| next() % span).collect();
+            let mut a: CroHashSet<u64> = a_keys.iter().copied().collect();
+            let b: CroHashSet<u64> = b_keys.iter().copied().collect();
+            let mut std_a: HashSet<u64> = a_keys.iter().copied().collect();
+            let std_b: HashSet<u64> = b_keys.iter().copied().collect();
+            for key in a_keys.iter().step_by(3) {
+                assert_eq!(a.remove(key), std_a.remove(key));
+            }
+
+            assert_eq!(a.iter().len(), std_a.len());
+            assert_eq!(sorted(&mut a.iter()), sorted(&mut std_a.iter()));
+            assert_eq!(sorted(&mut a.union(&b)), sorted(&mut std_a.union(&std_b)));
+            assert_eq!(sorted(&mut a.intersection(&b)), sorted(&mut std_a.intersection(&std_b)));
+            assert_eq!(sorted(&mut b.intersection(&a)), sorted(&mut std_b.intersection(&std_a)));
+            assert_eq!(sorted(&mut a.difference(&b)), sorted(&mut std_a.difference(&std_b)));
+            assert_eq!(sorted(&mut b.difference(&a)), sorted(&mut std_b.difference(&std_a)));
+            assert_eq!(
+                sorted(&mut a.symmetric_difference(&b)),
+                sorted(&mut std_a.symmetric_difference(&std_b))
+            );
+            assert_eq!(a.is_disjoint(&b), std_a.is_disjoint(&std_b));
+            assert_eq!(a.is_subset(&b), std_a.is_subset(&std_b));
+            assert_eq!(b.is_superset(&a), std_b.is_superset(&std_a));
+
+            check_size_hint(a.union(&b));
+            check_size_hint(a.intersection(&b));
+            check_size_hint(b.intersection(&a));
+            check_size_hint(a.difference(&b));
+            check_size_hint(a.symmetric_difference(&b));
+            assert!(a.intersection(&b).size_hint().1 <= Some(a.len().min(b.len())));
+
+            let mut drain = a.drain();
+            assert_eq!(drain.len(), std_a.len());
+            drain.next();
+            assert_eq!(drain.len(), std_a.len().saturating_sub(1));
+        }
+    }
+
+    #[test]
+    fn test_set_operators_match_std() {
+        use crate::cmp::CroReverseOrder;
+        use std::collections::{BTreeSet, HashSet};
+
+        let a_keys: Vec<u32> = (0..300).map(|x| x * 7 % 211).collect();
+        let b_keys: Vec<u32> = (0..200).map(|x| x * 5 % 163 + 40).collect();
+        let sorted = |set: &CroHashSet<u32>| {
+            let mut keys: Vec<u32> = set.iter().copied().collect();
+            keys.sort_unstable();
+            keys
+        };
+        let std_sorted = |set: HashSet<u32>| {
+            let mut keys: Vec<u32> = set.into_iter().collect();
+            keys.sort_unstable();
+            keys
+        };
+
+        let a: CroHashSet<u32> = a_keys.iter().copied().collect();
+        let b: CroHashSet<u32> = b_keys.iter().copied().collect();
+        let std_a: HashSet<u32> = a_keys.iter().copied().collect();
+        let std_b: HashSet<u32> = b_keys.iter().copied().collect();
+        assert_eq!(sorted(&(&a | &b)), std_sorted(&std_a | &std_b));
+        assert_eq!(sorted(&(&a & &b)), std_sorted(&std_a & &std_b));
+        assert_eq!(sorted(&(&a - &b)), std_sorted(&std_a - &std_b));
+        assert_eq!(sorted(&(&a ^ &b)), std_sorted(&std_a ^ &std_b));
+
+        let mut c = a.clone();
+        c |= &b;
+        assert_eq!(c, &a | &b);
+        let mut c = a.clone();
+        c &= &b;
+        assert_eq!(c, &a & &b);
+        let mut c = a.clone();
+        c -= &b;
+        assert_eq!(c, &a - &b);
+        c.insert(1000);
+        assert!(c.contains(&1000));
+        let mut c = a.clone();
+        c ^= &b;
+        assert_eq!(c, &a ^ &b);
+
+        let a: CroBTreeSet<u32> = a_keys.iter().copied().collect();
+        let b: CroBTreeSet<u32> = b_keys.iter().copied().collect();
+        let std_a: BTreeSet<u32> = a_keys.iter().copied().collect();
+        let std_b: BTreeSet<u32> = b_keys.iter().copied().collect();
+        assert!((&a | &b).iter().eq(&(&std_a | &std_b)));
+        assert!((&a & &b).iter().eq(&(&std_a & &std_b)));
+        assert!((&a - &b).iter().eq(&(&std_a - &std_b)));
+        assert!((&a ^ &b).iter().eq(&(&std_a ^ &std_b)));
+
+        let expected = [&std_a | &std_b, &std_a & &std_b, &std_a - &std_b, &std_a ^ &std_b];
+        for (op, expected) in expected.iter().enumerate() {
+            let mut c = a.clone();
+            match op {
+                0 => c |= &b,
+                1 => c &= &b,
+                2 => c -= &b,
+                _ => c ^= &b,
+            }
+            assert!(c.iter().eq(expected));
+            assert_eq!(c.len(), expected.len());
+        }
+
+        let mut rev_a = CroBTreeSet::with_order_and_comparator(4, CroReverseOrder);
+        let mut rev_b = CroBTreeSet::with_order_and_comparator(4, CroReverseOrder);
+        rev_a.extend(a_keys.iter().copied());
+        rev_b.extend(b_keys.iter().copied());
+        let rev_results = [&rev_a | &rev_b, &rev_a & &rev_b, &rev_a - &rev_b, &rev_a ^ &rev_b];
+        for (result, expected) in rev_results.iter().zip(&expected) {
+            result.validate().unwrap();
+            assert!(result.iter().eq(expected.iter().rev()));
+        }
+        let mut rev_union = &rev_a | &rev_b;
+        assert!(rev_union.iter().eq((&std_a | &std_b).iter().rev()));
+        rev_union.insert(10_000);
+        assert_eq!(rev_union.first(), Some(&10_000));
+    }
+
     #[test]
     fn test_hash_set_borrowed_lookup() {
         let mut set = CroHashSet::new();
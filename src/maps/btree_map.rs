@@ -1,88 +1,548 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::iter::Peekable;
+use std::marker::PhantomData;
 use std::mem;
-use crate::sequences::CroVec;
+use std::ops::{Bound, RangeBounds};
 
-#[derive(Debug)]
-struct Node<K, V> {
-    keys: CroVec<K>,
-    values: CroVec<V>,
-    children: CroVec<Node<K, V>>,
-    is_leaf: bool,
+use super::btree_node::{self, Handle, Root, SearchResult};
+use crate::cmp::{Comparator, CroNaturalOrder};
+use crate::invariants::{check_invariants, CroInvariantError};
+
+// Monoid folded over entries for `CroBTree::aggregate`: `combine` must be
+// associative with `identity` as its neutral element. It need not commute;
+// entries are combined in key order.
+pub trait Summary<K, V>: Clone {
+    fn identity() -> Self;
+    fn from_entry(key: &K, val: &V) -> Self;
+    fn combine(&self, other: &Self) -> Self;
+}
+
+impl<K, V> Summary<K, V> for () {
+    fn identity() -> Self {}
+
+    fn from_entry(_key: &K, _val: &V) -> Self {}
+
+    fn combine(&self, _other: &Self) -> Self {}
 }
 
-#[derive(Debug)]
-pub struct CroBTree<K, V> {
-    root: Option<Node<K, V>>,
+pub struct CroBTree<K, V, const B: usize = 6, S = (), C = CroNaturalOrder> {
+    root: Option<Root<K, V, B, S>>,
     order: usize,
     length: usize,
+    comparator: C,
+    _marker: PhantomData<Box<(K, V)>>,
 }
 
-impl<K, V> Node<K, V>
-where
-    K: Ord + Clone,
-    V: Clone,
-{
-    fn new(is_leaf: bool, order: usize) -> Self {
-        let mut keys = CroVec::new();
-        let mut values = CroVec::new();
-        let mut children = CroVec::new();
+pub struct CroBTreeIter<'a, K, V, const B: usize = 6, S = ()> {
+    front: Option<Handle<K, V, B, S>>,
+    back: Option<Handle<K, V, B, S>>,
+    remaining: usize,
+    _marker: PhantomData<&'a (K, V)>,
+}
+
+impl<'a, K, V, const B: usize, S> Iterator for CroBTreeIter<'a, K, V, B, S> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let kv = self.front?.next_kv()?;
+            self.front = Some(kv.right_leaf_edge());
+            Some((kv.key(), kv.val()))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V, const B: usize, S> DoubleEndedIterator for CroBTreeIter<'_, K, V, B, S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let kv = self.back?.prev_kv()?;
+            self.back = Some(kv.left_leaf_edge());
+            Some((kv.key(), kv.val()))
+        }
+    }
+}
+
+impl<K, V, const B: usize, S> ExactSizeIterator for CroBTreeIter<'_, K, V, B, S> {}
+
+pub struct CroBTreeIntoIter<K, V, const B: usize = 6, S = ()> {
+    root: Option<Root<K, V, B, S>>,
+    front: Option<Handle<K, V, B, S>>,
+    back: Option<Handle<K, V, B, S>>,
+    remaining: usize,
+}
+
+impl<K, V, const B: usize, S> Iterator for CroBTreeIntoIter<K, V, B, S> {
+    type Item = (K, V);
 
-        keys.reserve(order - 1);
-        values.reserve(order - 1);
-        if !is_leaf {
-            children.reserve(order);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let kv = self.front?.next_kv()?;
+            self.front = Some(kv.right_leaf_edge());
+            Some((
+                std::ptr::read(btree_node::key_ptr(kv.node, kv.idx)),
+                std::ptr::read(btree_node::val_ptr(kv.node, kv.idx)),
+            ))
         }
+    }
 
-        Node {
-            keys,
-            values,
-            children,
-            is_leaf,
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V, const B: usize, S> DoubleEndedIterator for CroBTreeIntoIter<K, V, B, S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let kv = self.back?.prev_kv()?;
+            self.back = Some(kv.left_leaf_edge());
+            Some((
+                std::ptr::read(btree_node::key_ptr(kv.node, kv.idx)),
+                std::ptr::read(btree_node::val_ptr(kv.node, kv.idx)),
+            ))
         }
     }
+}
 
-    fn is_full(&self, order: usize) -> bool {
-        debug_assert!(self.keys.size() <= order - 1);
-        self.keys.size() == order - 1
+impl<K, V, const B: usize, S> ExactSizeIterator for CroBTreeIntoIter<K, V, B, S> {}
+
+impl<K, V, const B: usize, S> Drop for CroBTreeIntoIter<K, V, B, S> {
+    fn drop(&mut self) {
+        for _ in self.by_ref() {}
+        if let Some(root) = self.root.take() {
+            unsafe { root.dealloc_all() };
+        }
     }
 }
 
-pub struct CroBTreeIter<'a, K, V> {
-    items: CroVec<(&'a K, &'a V)>,
-    index: usize,
+// Pair of leaf edges delimiting the entries still to be visited.
+struct LeafRange<K, V, const B: usize, S> {
+    front: Option<Handle<K, V, B, S>>,
+    back: Option<Handle<K, V, B, S>>,
 }
 
-impl<'a, K, V> Iterator for CroBTreeIter<'a, K, V> {
+impl<K, V, const B: usize, S> LeafRange<K, V, B, S> {
+    fn next_kv(&mut self) -> Option<Handle<K, V, B, S>> {
+        if self.front == self.back {
+            return None;
+        }
+        unsafe {
+            let kv = self.front?.next_kv()?;
+            self.front = Some(kv.right_leaf_edge());
+            Some(kv)
+        }
+    }
+
+    fn next_back_kv(&mut self) -> Option<Handle<K, V, B, S>> {
+        if self.front == self.back {
+            return None;
+        }
+        unsafe {
+            let kv = self.back?.prev_kv()?;
+            self.back = Some(kv.left_leaf_edge());
+            Some(kv)
+        }
+    }
+}
+
+pub struct CroBTreeRange<'a, K, V, const B: usize = 6, S = ()> {
+    range: LeafRange<K, V, B, S>,
+    _marker: PhantomData<&'a (K, V)>,
+}
+
+impl<'a, K, V, const B: usize, S> Iterator for CroBTreeRange<'a, K, V, B, S> {
     type Item = (&'a K, &'a V);
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.items.size() {
-            let item = self.items[self.index];
-            self.index += 1;
-            Some(*&item)
-        } else {
-            None
+        self.range.next_kv().map(|kv| unsafe { (kv.key(), kv.val()) })
+    }
+}
+
+impl<K, V, const B: usize, S> DoubleEndedIterator for CroBTreeRange<'_, K, V, B, S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.range.next_back_kv().map(|kv| unsafe { (kv.key(), kv.val()) })
+    }
+}
+
+pub struct CroBTreeRangeMut<'a, K, V, const B: usize = 6, S = ()> {
+    range: LeafRange<K, V, B, S>,
+    _marker: PhantomData<&'a mut (K, V)>,
+}
+
+impl<'a, K, V, const B: usize, S> Iterator for CroBTreeRangeMut<'a, K, V, B, S> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.range.next_kv().map(|kv| unsafe { (kv.key(), kv.val_mut()) })
+    }
+}
+
+impl<K, V, const B: usize, S> DoubleEndedIterator for CroBTreeRangeMut<'_, K, V, B, S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.range.next_back_kv().map(|kv| unsafe { (kv.key(), kv.val_mut()) })
+    }
+}
+
+// Cursors sit in the gap between two entries, or at either end of the tree.
+pub struct CroBTreeCursor<'a, K, V, const B: usize = 6, S = ()> {
+    edge: Option<Handle<K, V, B, S>>,
+    _marker: PhantomData<&'a (K, V)>,
+}
+
+impl<K, V, const B: usize, S> Clone for CroBTreeCursor<'_, K, V, B, S> {
+    fn clone(&self) -> Self {
+        CroBTreeCursor {
+            edge: self.edge,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V, const B: usize, S> CroBTreeCursor<'a, K, V, B, S> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let kv = unsafe { self.edge?.next_kv()? };
+        self.edge = Some(unsafe { kv.right_leaf_edge() });
+        Some(unsafe { (kv.key(), kv.val()) })
+    }
+
+    pub fn prev(&mut self) -> Option<(&'a K, &'a V)> {
+        let kv = unsafe { self.edge?.prev_kv()? };
+        self.edge = Some(unsafe { kv.left_leaf_edge() });
+        Some(unsafe { (kv.key(), kv.val()) })
+    }
+
+    pub fn peek_next(&self) -> Option<(&'a K, &'a V)> {
+        let kv = unsafe { self.edge?.next_kv()? };
+        Some(unsafe { (kv.key(), kv.val()) })
+    }
+
+    pub fn peek_prev(&self) -> Option<(&'a K, &'a V)> {
+        let kv = unsafe { self.edge?.prev_kv()? };
+        Some(unsafe { (kv.key(), kv.val()) })
+    }
+
+    // Skips forward over the entries matching `before`, which must already
+    // hold for everything behind the cursor. O(log d) for a move of d entries.
+    pub(crate) fn seek_forward<F>(&mut self, before: F)
+    where
+        F: FnMut(&K) -> bool,
+    {
+        if let Some(edge) = self.edge {
+            self.edge = Some(unsafe { btree_node::seek_leaf_edge(edge, before) });
         }
     }
 }
 
+pub struct CroBTreeCursorMut<'a, K, V, const B: usize = 6, S = (), C = CroNaturalOrder> {
+    tree: &'a mut CroBTree<K, V, B, S, C>,
+    edge: Option<Handle<K, V, B, S>>,
+}
+
+impl<K, V, const B: usize, S, C> CroBTreeCursorMut<'_, K, V, B, S, C>
+where
+    S: Summary<K, V>,
+{
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<(&K, &mut V)> {
+        let kv = unsafe { self.edge?.next_kv()? };
+        self.edge = Some(unsafe { kv.right_leaf_edge() });
+        Some(unsafe { (kv.key(), kv.val_mut()) })
+    }
+
+    pub fn prev(&mut self) -> Option<(&K, &mut V)> {
+        let kv = unsafe { self.edge?.prev_kv()? };
+        self.edge = Some(unsafe { kv.left_leaf_edge() });
+        Some(unsafe { (kv.key(), kv.val_mut()) })
+    }
+
+    pub fn peek_next(&mut self) -> Option<(&K, &mut V)> {
+        let kv = unsafe { self.edge?.next_kv()? };
+        Some(unsafe { (kv.key(), kv.val_mut()) })
+    }
+
+    pub fn peek_prev(&mut self) -> Option<(&K, &mut V)> {
+        let kv = unsafe { self.edge?.prev_kv()? };
+        Some(unsafe { (kv.key(), kv.val_mut()) })
+    }
+
+    pub fn as_cursor(&self) -> CroBTreeCursor<'_, K, V, B, S> {
+        CroBTreeCursor {
+            edge: self.edge,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn remove_next(&mut self) -> Option<(K, V)> {
+        let kv = unsafe { self.edge?.next_kv()? };
+        let (entry, edge) = self.tree.remove_kv_tracking(kv);
+        self.edge = Some(edge);
+        Some(entry)
+    }
+
+    pub fn remove_prev(&mut self) -> Option<(K, V)> {
+        let kv = unsafe { self.edge?.prev_kv()? };
+        let (entry, edge) = self.tree.remove_kv_tracking(kv);
+        self.edge = Some(edge);
+        Some(entry)
+    }
+}
+
+pub enum CroBTreeEntry<'a, K, V, const B: usize = 6, S = (), C = CroNaturalOrder> {
+    Occupied(CroBTreeOccupiedEntry<'a, K, V, B, S, C>),
+    Vacant(CroBTreeVacantEntry<'a, K, V, B, S, C>),
+}
+
+pub struct CroBTreeOccupiedEntry<'a, K, V, const B: usize = 6, S = (), C = CroNaturalOrder> {
+    tree: &'a mut CroBTree<K, V, B, S, C>,
+    handle: Handle<K, V, B, S>,
+}
+
+pub struct CroBTreeVacantEntry<'a, K, V, const B: usize = 6, S = (), C = CroNaturalOrder> {
+    tree: &'a mut CroBTree<K, V, B, S, C>,
+    key: K,
+    // Leaf edge found during the search; `None` while the tree has no root.
+    edge: Option<Handle<K, V, B, S>>,
+}
+
+impl<'a, K, V, const B: usize, S, C> CroBTreeEntry<'a, K, V, B, S, C>
+where
+    S: Summary<K, V>,
+{
+    pub fn key(&self) -> &K {
+        match self {
+            CroBTreeEntry::Occupied(entry) => entry.key(),
+            CroBTreeEntry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            CroBTreeEntry::Occupied(entry) => entry.into_mut(),
+            CroBTreeEntry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            CroBTreeEntry::Occupied(entry) => entry.into_mut(),
+            CroBTreeEntry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        if let CroBTreeEntry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+
+    pub fn insert(self, value: V) -> CroBTreeOccupiedEntry<'a, K, V, B, S, C> {
+        match self {
+            CroBTreeEntry::Occupied(mut entry) => {
+                entry.insert(value);
+                entry
+            }
+            CroBTreeEntry::Vacant(entry) => entry.insert_entry(value),
+        }
+    }
+}
+
+impl<'a, K, V, const B: usize, S, C> CroBTreeOccupiedEntry<'a, K, V, B, S, C>
+where
+    S: Summary<K, V>,
+{
+    pub fn key(&self) -> &K {
+        unsafe { self.handle.key() }
+    }
+
+    pub fn get(&self) -> &V {
+        unsafe { self.handle.val() }
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe { self.handle.val_mut() }
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        unsafe { self.handle.val_mut() }
+    }
+
+    // Replaces in place and refreshes the summaries above right away, rather
+    // than marking them stale as `get_mut` has to.
+    pub fn insert(&mut self, value: V) -> V {
+        let handle = self.handle;
+        let old = mem::replace(unsafe { &mut *btree_node::val_ptr(handle.node, handle.idx) }, value);
+        unsafe { btree_node::resummarize_path(handle.node, handle.height) };
+        old
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        self.tree.remove_kv(self.handle)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+}
+
+impl<'a, K, V, const B: usize, S, C> CroBTreeVacantEntry<'a, K, V, B, S, C>
+where
+    S: Summary<K, V>,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.insert_entry(value).into_mut()
+    }
+
+    fn insert_entry(self, value: V) -> CroBTreeOccupiedEntry<'a, K, V, B, S, C> {
+        let tree = self.tree;
+        let handle = tree.insert_at(self.edge, self.key, value);
+        CroBTreeOccupiedEntry { tree, handle }
+    }
+}
+
 impl<K, V> CroBTree<K, V>
 where
-    K: Ord + Clone,
-    V: Clone,
+    K: Ord,
 {
     pub fn new() -> Self {
         Self::with_order(6)
     }
 
     pub fn with_order(order: usize) -> Self {
+        Self::with_node_order(order, CroNaturalOrder)
+    }
+}
+
+impl<K, V, C> CroBTree<K, V, 6, (), C>
+where
+    C: Comparator<K>,
+{
+    pub fn with_comparator(comparator: C) -> Self {
+        Self::with_node_order(6, comparator)
+    }
+
+    pub fn with_order_and_comparator(order: usize, comparator: C) -> Self {
+        Self::with_node_order(order, comparator)
+    }
+}
+
+impl<K, V, const B: usize, S, C> CroBTree<K, V, B, S, C>
+where
+    S: Summary<K, V>,
+    C: Comparator<K>,
+{
+    pub fn with_summary() -> Self
+    where
+        C: Default,
+    {
+        Self::with_node_order(B, C::default())
+    }
+
+    pub fn with_summary_and_order(order: usize) -> Self
+    where
+        C: Default,
+    {
+        Self::with_node_order(order, C::default())
+    }
+
+    pub fn with_summary_and_comparator(comparator: C) -> Self {
+        Self::with_node_order(B, comparator)
+    }
+
+    pub fn with_summary_order_and_comparator(order: usize, comparator: C) -> Self {
+        Self::with_node_order(order, comparator)
+    }
+
+    // Keys must be ascending under the tree's comparator.
+    pub fn from_sorted_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        C: Default,
+    {
+        let mut tree = Self::with_summary();
+        tree.bulk_load(iter.into_iter(), tree.order - 1);
+        tree
+    }
+
+    // `fill` is the number of entries put in each node, between the minimum
+    // and maximum for the order; lower fills leave room for later inserts.
+    pub fn from_sorted_iter_with_fill<I>(iter: I, fill: usize) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        C: Default,
+    {
+        let mut tree = Self::with_summary();
+        tree.bulk_load(iter.into_iter(), fill);
+        tree
+    }
+}
+
+impl<K, V, const B: usize, S, C> CroBTree<K, V, B, S, C> {
+    // Any order works; orders above `B` get nodes sized to fit.
+    fn with_node_order(order: usize, comparator: C) -> Self {
         assert!(order >= 3, "B-tree order must be at least 3");
+        assert!(u32::try_from(order).is_ok(), "B-tree order must fit in 32 bits");
         CroBTree {
             root: None,
             order,
             length: 0,
+            comparator,
+            _marker: PhantomData,
         }
     }
 
+    // Empty tree with the same order and comparator.
+    fn new_like(&self) -> Self
+    where
+        C: Clone,
+    {
+        Self::with_node_order(self.order, self.comparator.clone())
+    }
+
     pub fn len(&self) -> usize {
         self.length
     }
@@ -95,198 +555,786 @@ where
         self.order
     }
 
+    pub fn comparator(&self) -> &C {
+        &self.comparator
+    }
+
     pub fn wipe(&mut self) {
-        self.root = None;
+        if let Some(root) = self.root.take() {
+            unsafe { root.drop_all() };
+        }
         self.length = 0;
+        check_invariants!(self.validate_structure());
     }
 
-    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
-        if self.root.is_none() {
-            self.root = Some(Node::new(true, self.order));
-        }
+    // `validate` without key order, for mutations made without the bound to
+    // compare keys. Everything else it checks is the same.
+    fn validate_structure(&self) -> Result<(), CroInvariantError> {
+        self.validate_under(|_, _| true)
+    }
 
-        let is_root_full = self.root.as_ref().unwrap().is_full(self.order);
+    fn validate_under<F>(&self, less: F) -> Result<(), CroInvariantError>
+    where
+        F: Fn(&K, &K) -> bool,
+    {
+        let counted = match &self.root {
+            Some(root) => unsafe { btree_node::validate_subtree(root.node, root.height, self.order, true, &less)? },
+            None => 0,
+        };
+        if counted != self.length {
+            return Err(CroInvariantError::LenMismatch {
+                stored: self.length,
+                counted,
+            });
+        }
+        Ok(())
+    }
 
-        if is_root_full {
-            let mut new_root = Node::new(false, self.order);
-            let old_root = self.root.take().unwrap();
-            new_root.children.push(old_root);
-            self.split_child(&mut new_root, 0);
-            self.root = Some(new_root);
+    pub fn iter(&self) -> CroBTreeIter<'_, K, V, B, S> {
+        let (front, back) = match &self.root {
+            Some(root) => unsafe { (Some(root.first_leaf_edge()), Some(root.last_leaf_edge())) },
+            None => (None, None),
+        };
+        CroBTreeIter {
+            front,
+            back,
+            remaining: self.length,
+            _marker: PhantomData,
         }
+    }
+
+    fn first_kv(&self) -> Option<Handle<K, V, B, S>> {
+        let root = self.root.as_ref()?;
+        unsafe { root.first_leaf_edge().next_kv() }
+    }
 
-        let mut root = self.root.take().unwrap();
+    fn last_kv(&self) -> Option<Handle<K, V, B, S>> {
+        let root = self.root.as_ref()?;
+        unsafe { root.last_leaf_edge().prev_kv() }
+    }
+
+    fn remove_kv(&mut self, kv: Handle<K, V, B, S>) -> (K, V)
+    where
+        S: Summary<K, V>,
+    {
+        self.remove_kv_tracking(kv).0
+    }
+
+    fn remove_kv_tracking(&mut self, kv: Handle<K, V, B, S>) -> ((K, V), Handle<K, V, B, S>)
+    where
+        S: Summary<K, V>,
+    {
         let order = self.order;
-        let result = self.insert_non_full(&mut root, key, value, order);
-        self.root = Some(root);
+        let root = self.root.as_mut().unwrap();
+        let removed = unsafe { btree_node::remove_kv_tracking(kv, order, root) };
+        self.length -= 1;
+        check_invariants!(self.validate_structure());
+        removed
+    }
+
+    // Inserts at a leaf edge from an earlier search, splitting on the way up.
+    fn insert_at(&mut self, edge: Option<Handle<K, V, B, S>>, key: K, value: V) -> Handle<K, V, B, S>
+    where
+        S: Summary<K, V>,
+    {
+        let edge = match edge {
+            Some(edge) => edge,
+            None => {
+                let root = self.root.insert(Root::new_leaf(self.order));
+                Handle::new(root.node, 0, 0)
+            }
+        };
+        let order = self.order;
+        let root = self.root.as_mut().unwrap();
+        let handle = unsafe { btree_node::insert_recursing(edge, key, value, order, root) };
+        self.length += 1;
+        check_invariants!(self.validate_structure());
+        handle
+    }
+
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.first_kv().map(|kv| unsafe { (kv.key(), kv.val()) })
+    }
+
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.last_kv().map(|kv| unsafe { (kv.key(), kv.val()) })
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)>
+    where
+        S: Summary<K, V>,
+    {
+        let kv = self.first_kv()?;
+        Some(self.remove_kv(kv))
+    }
 
-        if result.is_none() {
-            self.length += 1;
+    pub fn pop_last(&mut self) -> Option<(K, V)>
+    where
+        S: Summary<K, V>,
+    {
+        let kv = self.last_kv()?;
+        Some(self.remove_kv(kv))
+    }
+
+    // Single pass in key order; each removal rebalances locally and hands
+    // back the edge to continue from.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        S: Summary<K, V>,
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        let mut edge = match &self.root {
+            Some(root) => unsafe { root.first_leaf_edge() },
+            None => return,
+        };
+        while let Some(kv) = unsafe { edge.next_kv() } {
+            let keep = unsafe { f(kv.key(), kv.val_mut()) };
+            edge = if keep {
+                unsafe { kv.right_leaf_edge() }
+            } else {
+                self.remove_kv_tracking(kv).1
+            };
         }
-        result
     }
 
-    fn insert_non_full(&mut self, node: &mut Node<K, V>, key: K, value: V, order: usize) -> Option<V> {
-        let mut i = node.keys.size();
+    fn select_kv(&self, index: usize) -> Option<Handle<K, V, B, S>> {
+        if index >= self.length {
+            return None;
+        }
+        let root = self.root.as_ref()?;
+        Some(unsafe { btree_node::select(root.node, root.height, index) })
+    }
 
-        if node.is_leaf {
+    pub fn select(&self, index: usize) -> Option<(&K, &V)> {
+        self.get_index(index)
+    }
 
-            while i > 0 && key < node.keys[i - 1] {
-                i -= 1;
-            }
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        self.select_kv(index).map(|kv| unsafe { (kv.key(), kv.val()) })
+    }
 
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
+        self.select_kv(index).map(|kv| unsafe { (kv.key(), kv.val_mut()) })
+    }
 
-            if i > 0 && node.keys[i - 1] == key {
-                return Some(mem::replace(&mut node.values[i - 1], value));
+    // Leaf edge just before the entry at `index`, or after the last entry.
+    fn edge_at_index(&self, index: usize) -> Option<Handle<K, V, B, S>> {
+        let root = self.root.as_ref()?;
+        unsafe {
+            match self.select_kv(index) {
+                Some(kv) => Some(kv.left_leaf_edge()),
+                None => Some(root.last_leaf_edge()),
             }
+        }
+    }
 
+    pub fn range_by_index<R>(&self, range: R) -> CroBTreeRange<'_, K, V, B, S>
+    where
+        R: RangeBounds<usize>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.length,
+        };
+        assert!(start <= end, "index range starts at {} but ends at {}", start, end);
+        assert!(end <= self.length, "index range end {} out of bounds for length {}", end, self.length);
+        CroBTreeRange {
+            range: LeafRange {
+                front: self.edge_at_index(start),
+                back: self.edge_at_index(end),
+            },
+            _marker: PhantomData,
+        }
+    }
+
+    // Attaches `tail`, whose keys all sort after ours, in O(log n).
+    fn join_after(&mut self, mut tail: Self)
+    where
+        S: Summary<K, V>,
+    {
+        if tail.is_empty() {
+            return;
+        }
+        if self.is_empty() {
+            mem::swap(&mut self.root, &mut tail.root);
+            mem::swap(&mut self.length, &mut tail.length);
+            return;
+        }
+        let (key, value) = tail.pop_first().unwrap();
+        if tail.is_empty() {
+            let edge = unsafe { self.root.as_ref().unwrap().last_leaf_edge() };
+            self.insert_at(Some(edge), key, value);
+            return;
+        }
+        let left = self.root.take().unwrap();
+        let right = tail.root.take().unwrap();
+        self.root = Some(unsafe { btree_node::join(left, key, value, right, self.order) });
+        self.length += mem::take(&mut tail.length) + 1;
+        check_invariants!(self.validate_structure());
+    }
 
-            node.keys.insert(i, key);
-            node.values.insert(i, value);
-            None
-        } else {
-            while i > 0 && key < node.keys[i - 1] {
-                i -= 1;
+    // Splits off every entry for which `before` fails.
+    fn split_off_where<F>(&mut self, before: F) -> Self
+    where
+        C: Clone,
+        S: Summary<K, V>,
+        F: FnMut(&K) -> bool,
+    {
+        let mut right = self.new_like();
+        let Some(root) = self.root.as_mut() else {
+            return right;
+        };
+        let right_root = unsafe { btree_node::split_root(root, self.order, before) };
+        let right_len = unsafe { btree_node::subtree_len(right_root.node, right_root.height) };
+        self.length -= right_len;
+        right.root = Some(right_root);
+        right.length = right_len;
+        check_invariants!(self.validate_structure());
+        check_invariants!(right.validate_structure());
+        right
+    }
+}
+
+impl<K, V, const B: usize, S, C> CroBTree<K, V, B, S, C>
+where
+    C: Comparator<K>,
+    S: Summary<K, V>,
+{
+    fn search<Q>(&self, key: &Q) -> Option<SearchResult<K, V, B, S>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        let root = self.root.as_ref()?;
+        let comparator = &self.comparator;
+        let cmp = |stored: &K| comparator.compare(key, stored.borrow());
+        Some(unsafe { btree_node::search_tree(root.node, root.height, cmp) })
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<Handle<K, V, B, S>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        match self.search(key)? {
+            SearchResult::Found(kv) => Some(kv),
+            SearchResult::GoDown(_) => None,
+        }
+    }
+
+    // Checks occupancy, parent links, cached subtree sizes, the stored
+    // length and that keys ascend under the comparator.
+    pub fn validate(&self) -> Result<(), CroInvariantError> {
+        self.validate_under(|a, b| self.comparator.compare(a, b).is_lt())
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let old = match self.entry(key) {
+            CroBTreeEntry::Occupied(mut entry) => Some(entry.insert(value)),
+            CroBTreeEntry::Vacant(entry) => {
+                entry.insert(value);
+                None
             }
+        };
+        check_invariants!(self.validate());
+        old
+    }
 
-            if i > 0 && node.keys[i - 1] == key {
-                return Some(mem::replace(&mut node.values[i - 1], value));
+    pub fn entry(&mut self, key: K) -> CroBTreeEntry<'_, K, V, B, S, C> {
+        match self.search(&key) {
+            Some(SearchResult::Found(handle)) => {
+                CroBTreeEntry::Occupied(CroBTreeOccupiedEntry { tree: self, handle })
             }
+            Some(SearchResult::GoDown(edge)) => CroBTreeEntry::Vacant(CroBTreeVacantEntry {
+                tree: self,
+                key,
+                edge: Some(edge),
+            }),
+            None => CroBTreeEntry::Vacant(CroBTreeVacantEntry {
+                tree: self,
+                key,
+                edge: None,
+            }),
+        }
+    }
 
-            let child = &mut node.children[i];
+    // Leaf edge just before the first entry inside `bound`.
+    fn lower_edge<Q>(&self, bound: Bound<&Q>) -> Option<Handle<K, V, B, S>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        let root = self.root.as_ref()?;
+        let comparator = &self.comparator;
+        let edge = unsafe {
+            match bound {
+                Bound::Included(key) => {
+                    let before = |stored: &K| comparator.compare(stored.borrow(), key).is_lt();
+                    btree_node::partition_leaf_edge(root.node, root.height, before)
+                }
+                Bound::Excluded(key) => {
+                    let before = |stored: &K| comparator.compare(stored.borrow(), key).is_le();
+                    btree_node::partition_leaf_edge(root.node, root.height, before)
+                }
+                Bound::Unbounded => root.first_leaf_edge(),
+            }
+        };
+        Some(edge)
+    }
 
-            if child.is_full(order) {
-                self.split_child(node, i);
-                if key == node.keys[i] {
-                    return Some(mem::replace(&mut node.values[i], value));
+    // Leaf edge just after the last entry inside `bound`.
+    fn upper_edge<Q>(&self, bound: Bound<&Q>) -> Option<Handle<K, V, B, S>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        let root = self.root.as_ref()?;
+        let comparator = &self.comparator;
+        let edge = unsafe {
+            match bound {
+                Bound::Included(key) => {
+                    let before = |stored: &K| comparator.compare(stored.borrow(), key).is_le();
+                    btree_node::partition_leaf_edge(root.node, root.height, before)
                 }
-                if key > node.keys[i] {
-                    i += 1;
+                Bound::Excluded(key) => {
+                    let before = |stored: &K| comparator.compare(stored.borrow(), key).is_lt();
+                    btree_node::partition_leaf_edge(root.node, root.height, before)
                 }
+                Bound::Unbounded => root.last_leaf_edge(),
             }
-            self.insert_non_full(&mut node.children[i], key, value, order)
+        };
+        Some(edge)
+    }
+
+    fn leaf_range<T, R>(&self, range: &R) -> LeafRange<K, V, B, S>
+    where
+        K: Borrow<T>,
+        T: ?Sized,
+        C: Comparator<T>,
+        R: RangeBounds<T>,
+    {
+        check_range(range, &self.comparator);
+        LeafRange {
+            front: self.lower_edge(range.start_bound()),
+            back: self.upper_edge(range.end_bound()),
         }
+    }
 
+    pub fn range<T, R>(&self, range: R) -> CroBTreeRange<'_, K, V, B, S>
+    where
+        K: Borrow<T>,
+        T: ?Sized,
+        C: Comparator<T>,
+        R: RangeBounds<T>,
+    {
+        CroBTreeRange {
+            range: self.leaf_range(&range),
+            _marker: PhantomData,
+        }
     }
 
-    fn split_child(&mut self, parent: &mut Node<K, V>, child_index: usize) {
-        let child = &mut parent.children[child_index];
-        let mid = (self.order - 1) / 2;
+    pub fn range_mut<T, R>(&mut self, range: R) -> CroBTreeRangeMut<'_, K, V, B, S>
+    where
+        K: Borrow<T>,
+        T: ?Sized,
+        C: Comparator<T>,
+        R: RangeBounds<T>,
+    {
+        CroBTreeRangeMut {
+            range: self.leaf_range(&range),
+            _marker: PhantomData,
+        }
+    }
 
-        let mut new_node = Node::new(child.is_leaf, self.order);
+    pub fn lower_bound<Q>(&self, bound: Bound<&Q>) -> CroBTreeCursor<'_, K, V, B, S>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        CroBTreeCursor {
+            edge: self.lower_edge(bound),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn lower_bound_mut<Q>(&mut self, bound: Bound<&Q>) -> CroBTreeCursorMut<'_, K, V, B, S, C>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        let edge = self.lower_edge(bound);
+        CroBTreeCursorMut { tree: self, edge }
+    }
 
-        while child.keys.size() > mid + 1 {
-            let key = child.keys.pop().unwrap();
-            let value = child.values.pop().unwrap();
-            new_node.keys.insert(0, key);
-            new_node.values.insert(0, value);
+    pub fn upper_bound<Q>(&self, bound: Bound<&Q>) -> CroBTreeCursor<'_, K, V, B, S>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        CroBTreeCursor {
+            edge: self.upper_edge(bound),
+            _marker: PhantomData,
         }
+    }
 
-        if !child.is_leaf {
-            while child.children.size() > mid + 1 {
-                let moved_child = child.children.pop().unwrap();
-                new_node.children.insert(0, moved_child);
-            }
+    pub fn upper_bound_mut<Q>(&mut self, bound: Bound<&Q>) -> CroBTreeCursorMut<'_, K, V, B, S, C>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        let edge = self.upper_edge(bound);
+        CroBTreeCursorMut { tree: self, edge }
+    }
+
+    pub(crate) fn bulk_load<I>(&mut self, iter: I, fill: usize)
+    where
+        I: Iterator<Item = (K, V)>,
+    {
+        let min = (self.order - 1) / 2;
+        assert!(
+            fill >= min.max(1) && fill < self.order,
+            "node fill must be between {} and {} for order {}",
+            min.max(1),
+            self.order - 1,
+            self.order
+        );
+        self.wipe();
+        let root = self.root.insert(Root::new_leaf(self.order));
+        let iter = DedupSorted {
+            iter: iter.peekable(),
+            comparator: self.comparator.clone(),
+        };
+        self.length = unsafe { btree_node::bulk_push(root, iter, fill, self.order) };
+        check_invariants!(self.validate());
+    }
+
+    pub fn append(&mut self, other: &mut Self) {
+        if other.is_empty() {
+            return;
         }
+        if self.is_empty() && self.order == other.order {
+            mem::swap(self, other);
+            return;
+        }
+        let order = self.order;
+        let ours = mem::replace(self, self.new_like()).into_iter();
+        let theirs = mem::replace(other, other.new_like()).into_iter();
+        let merged = MergeSorted {
+            left: ours.peekable(),
+            right: theirs.peekable(),
+            comparator: self.comparator.clone(),
+        };
+        self.bulk_load(merged, order - 1);
+    }
 
-        let mid_key = child.keys.pop().unwrap();
-        let mid_value = child.values.pop().unwrap();
+    pub fn split_off<Q>(&mut self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        let comparator = self.comparator.clone();
+        self.split_off_where(|stored| comparator.compare(stored.borrow(), key).is_lt())
+    }
 
-        parent.keys.insert(child_index, mid_key);
-        parent.values.insert(child_index, mid_value);
-        parent.children.insert(child_index + 1, new_node);
+    pub fn drain_range<T, R>(&mut self, range: R) -> CroBTreeIntoIter<K, V, B, S>
+    where
+        K: Borrow<T>,
+        T: ?Sized,
+        C: Comparator<T>,
+        R: RangeBounds<T>,
+    {
+        check_range(&range, &self.comparator);
+        let comparator = self.comparator.clone();
+        let mut middle = match range.start_bound() {
+            Bound::Included(start) => {
+                self.split_off_where(|stored| comparator.compare(stored.borrow(), start).is_lt())
+            }
+            Bound::Excluded(start) => {
+                self.split_off_where(|stored| comparator.compare(stored.borrow(), start).is_le())
+            }
+            Bound::Unbounded => mem::replace(self, self.new_like()),
+        };
+        let tail = match range.end_bound() {
+            Bound::Included(end) => {
+                middle.split_off_where(|stored| comparator.compare(stored.borrow(), end).is_le())
+            }
+            Bound::Excluded(end) => {
+                middle.split_off_where(|stored| comparator.compare(stored.borrow(), end).is_lt())
+            }
+            Bound::Unbounded => self.new_like(),
+        };
+        self.join_after(tail);
+        middle.into_iter()
+    }
+
+    pub fn remove_range<T, R>(&mut self, range: R) -> usize
+    where
+        K: Borrow<T>,
+        T: ?Sized,
+        C: Comparator<T>,
+        R: RangeBounds<T>,
+    {
+        self.drain_range(range).len()
     }
 
-    pub fn get(&self, key: &K) -> Option<&V> {
+    // Number of entries with keys below `key`.
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
         match &self.root {
-            None => None,
-            Some(node) => self.get_in_node(node, key),
+            Some(root) => unsafe {
+                btree_node::rank(root.node, root.height, |stored| {
+                    self.comparator.compare(stored.borrow(), key).is_lt()
+                })
+            },
+            None => 0,
         }
     }
 
-    fn get_in_node<'a>(&self, node: &'a Node<K, V>, key: &K) -> Option<&'a V> {
-        let mut i = 0;
-        while i < node.keys.size() && key > &node.keys[i] {
-            i += 1;
-        }
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        self.find(key).map(|kv| unsafe { kv.val() })
+    }
 
-        if i < node.keys.size() && key == &node.keys[i] {
-            Some(&node.values[i])
-        } else if node.is_leaf {
-            None
-        } else {
-            self.get_in_node(&node.children[i], key)
-        }
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        self.find(key).map(|kv| unsafe { kv.val_mut() })
     }
 
-    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
-        match &mut self.root {
-            None => None,
-            Some(node) => Self::get_mut_in_node(node, key),
-        }
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        self.find(key).is_some()
     }
 
-    fn get_mut_in_node<'a>(node: &'a mut Node<K, V>, key: &K) -> Option<&'a mut V> {
-        let mut i = 0;
-        while i < node.keys.size() && key > &node.keys[i] {
-            i += 1;
-        }
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        self.find(key).map(|kv| unsafe { (kv.key(), kv.val()) })
+    }
 
-        if i < node.keys.size() && key == &node.keys[i] {
-            Some(&mut node.values[i])
-        } else if node.is_leaf {
-            None
-        } else {
-            Self::get_mut_in_node(&mut node.children[i], key)
-        }
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        let kv = self.find(key)?;
+        Some(self.remove_kv(kv))
     }
+}
 
-    pub fn contains_key(&self, key: &K) -> bool {
-        self.get(key).is_some()
+impl<K, V, const B: usize, S, C> CroBTree<K, V, B, S, C>
+where
+    C: Comparator<K>,
+    S: Summary<K, V>,
+{
+    // Combined summary of the entries in `range`, in O(log n): inserts and
+    // removals keep the subtree summaries cached in the nodes current. Values
+    // changed through `&mut V` leave stale caches above them, and the first
+    // query to reach those refills them.
+    pub fn aggregate<T, R>(&self, range: R) -> S
+    where
+        K: Borrow<T>,
+        T: ?Sized,
+        C: Comparator<T>,
+        R: RangeBounds<T>,
+    {
+        check_range(&range, &self.comparator);
+        let root = match &self.root {
+            Some(root) => root,
+            None => return S::identity(),
+        };
+        let before_start = |stored: &K| match range.start_bound() {
+            Bound::Included(key) => self.comparator.compare(stored.borrow(), key).is_lt(),
+            Bound::Excluded(key) => self.comparator.compare(stored.borrow(), key).is_le(),
+            Bound::Unbounded => false,
+        };
+        let before_end = |stored: &K| match range.end_bound() {
+            Bound::Included(key) => self.comparator.compare(stored.borrow(), key).is_le(),
+            Bound::Excluded(key) => self.comparator.compare(stored.borrow(), key).is_lt(),
+            Bound::Unbounded => true,
+        };
+        unsafe { btree_node::summarize_range(root.node, root.height, before_start, before_end) }
     }
+}
 
-    pub fn iter(&self) -> CroBTreeIter<'_, K, V> {
-        let mut items = CroVec::new();
-        if let Some(root) = &self.root {
-            Self::collect_in_order(root, &mut items);
+fn check_range<T, R, C>(range: &R, comparator: &C)
+where
+    T: ?Sized,
+    R: RangeBounds<T>,
+    C: Comparator<T>,
+{
+    match (range.start_bound(), range.end_bound()) {
+        (Bound::Excluded(start), Bound::Excluded(end)) if comparator.compare(start, end).is_eq() => {
+            panic!("range start and end are equal and excluded in CroBTree")
+        }
+        (
+            Bound::Included(start) | Bound::Excluded(start),
+            Bound::Included(end) | Bound::Excluded(end),
+        ) if comparator.compare(start, end).is_gt() => {
+            panic!("range start is greater than range end in CroBTree")
         }
-        CroBTreeIter { items, index: 0 }
+        _ => {}
     }
+}
 
-    fn collect_in_order<'a>(
-        node: &'a Node<K, V>,
-        out: &mut CroVec<(&'a K, &'a V)>,
-    ) {
-        let key_count = node.keys.size();
-        if node.is_leaf {
-            for i in 0..key_count {
-                out.push((&node.keys[i], &node.values[i]));
+// Drops all but the last of each run of equal keys and rejects unsorted input.
+struct DedupSorted<K, V, I, C>
+where
+    I: Iterator<Item = (K, V)>,
+{
+    iter: Peekable<I>,
+    comparator: C,
+}
+
+impl<K, V, I, C> Iterator for DedupSorted<K, V, I, C>
+where
+    I: Iterator<Item = (K, V)>,
+    C: Comparator<K>,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut current = self.iter.next()?;
+        loop {
+            match self.iter.peek() {
+                None => return Some(current),
+                Some((next, _)) => match self.comparator.compare(&current.0, next) {
+                    Ordering::Less => return Some(current),
+                    Ordering::Equal => current = self.iter.next().unwrap(),
+                    Ordering::Greater => panic!("CroBTree bulk load requires keys in ascending order"),
+                },
             }
-            return;
         }
+    }
+}
+
+// Interleaves two sorted streams; on equal keys the left entry comes first.
+struct MergeSorted<K, V, L, R, C>
+where
+    L: Iterator<Item = (K, V)>,
+    R: Iterator<Item = (K, V)>,
+{
+    left: Peekable<L>,
+    right: Peekable<R>,
+    comparator: C,
+}
+
+impl<K, V, L, R, C> Iterator for MergeSorted<K, V, L, R, C>
+where
+    C: Comparator<K>,
+    L: Iterator<Item = (K, V)>,
+    R: Iterator<Item = (K, V)>,
+{
+    type Item = (K, V);
 
-        for i in 0..key_count {
-            Self::collect_in_order(&node.children[i], out);
-            out.push((&node.keys[i], &node.values[i]));
+    fn next(&mut self) -> Option<Self::Item> {
+        match (self.left.peek(), self.right.peek()) {
+            (Some((left, _)), Some((right, _))) if self.comparator.compare(left, right).is_gt() => {
+                self.right.next()
+            }
+            (Some(_), _) => self.left.next(),
+            (None, _) => self.right.next(),
         }
+    }
+}
+
+impl<K, V, const B: usize, S, C> Default for CroBTree<K, V, B, S, C>
+where
+    C: Default,
+{
+    fn default() -> Self {
+        Self::with_node_order(B, C::default())
+    }
+}
+
+impl<K, V, const B: usize, S, C> fmt::Debug for CroBTree<K, V, B, S, C>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, const B: usize, S, C> IntoIterator for CroBTree<K, V, B, S, C> {
+    type Item = (K, V);
+    type IntoIter = CroBTreeIntoIter<K, V, B, S>;
 
-        if node.children.size() > 0 {
-            Self::collect_in_order(&node.children[key_count], out);
+    fn into_iter(mut self) -> Self::IntoIter {
+        let root = self.root.take();
+        let (front, back) = match &root {
+            Some(root) => unsafe { (Some(root.first_leaf_edge()), Some(root.last_leaf_edge())) },
+            None => (None, None),
+        };
+        let remaining = mem::take(&mut self.length);
+        CroBTreeIntoIter {
+            root,
+            front,
+            back,
+            remaining,
         }
     }
 }
 
-impl<K, V> Drop for Node<K, V> {
+impl<K, V, const B: usize, S, C> Drop for CroBTree<K, V, B, S, C> {
     fn drop(&mut self) {
-        self.keys.wipe();
-        self.values.wipe();
-        self.children.wipe();
+        self.wipe();
     }
 }
 
-impl<K, V> Drop for CroBTree<K, V> {
-    fn drop(&mut self) {
-        self.root.take();
-        self.length = 0;
+// A `BTreeMap` iterates in ascending key order, so it bulk loads.
+impl<K: Ord, V> From<BTreeMap<K, V>> for CroBTree<K, V> {
+    fn from(map: BTreeMap<K, V>) -> Self {
+        CroBTree::from_sorted_iter(map)
+    }
+}
+
+impl<K: Ord, V, const B: usize, S, C> From<CroBTree<K, V, B, S, C>> for BTreeMap<K, V> {
+    fn from(tree: CroBTree<K, V, B, S, C>) -> Self {
+        tree.into_iter().collect()
     }
 }
@@ -0,0 +1,1528 @@
+use std::alloc::{self, Layout};
+use std::cell::UnsafeCell;
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr::{self, NonNull};
+
+use super::btree_map::Summary;
+use crate::invariants::CroInvariantError;
+
+// Raw node storage for `CroBTree`. A node of order `order` holds at most
+// `order - 1` entries and `order` children. Each node is a single allocation:
+// a `LeafNode` header followed by the key and value arrays, then for internal
+// nodes the edge array and an `InternalNode` trailer. The header sits first
+// and keys directly after it in both layouts, so a pointer to either can be
+// treated as a leaf. Trees of order up to `B` get `B - 1` slots per node, so
+// the arrays sit at fixed offsets; larger runtime orders size their nodes to
+// fit and record the slot count in the header.
+// Internal nodes also cache the summary `S` of their subtree. Every change to
+// the tree's shape or entries recomputes the caches along the path it touched,
+// from the children's caches, so it costs O(B) per level on top of the size
+// counts. Handing out `&mut V` is the exception: the value may change after we
+// return, so the caches above it are marked stale (`None`) and refilled by the
+// next `summarize` that reaches them. A run of k such edits therefore costs
+// O(k log n) up front plus one refill of the stale nodes, O(k B log n) at
+// worst, which later mutations or summary queries pay once.
+
+// The counts are 32-bit so the header stays small enough for a default node's
+// keys to share its first cache line.
+pub(crate) struct LeafNode<K, V, const B: usize, S> {
+    parent: Option<NodePtr<K, V, B, S>>,
+    parent_idx: u32,
+    len: u32,
+    // Entries the arrays have room for.
+    cap: u32,
+    _marker: PhantomData<(K, V, S)>,
+}
+
+pub(crate) struct InternalNode<S> {
+    // Number of entries in this subtree, for order statistics.
+    size: usize,
+    // Written by `summarize` through `&self`; nothing hands out references
+    // into it.
+    summary: UnsafeCell<Option<S>>,
+}
+
+pub(crate) type NodePtr<K, V, const B: usize, S> = NonNull<LeafNode<K, V, B, S>>;
+
+fn round_up(offset: usize, align: usize) -> usize {
+    (offset + align - 1) & !(align - 1)
+}
+
+// Byte offsets of the arrays and trailer in a node with room for `cap`
+// entries. They match the layout built by `node_layout`, which checks the
+// arithmetic once when the node is allocated.
+fn keys_offset<K, V, const B: usize, S>() -> usize {
+    round_up(mem::size_of::<LeafNode<K, V, B, S>>(), mem::align_of::<K>())
+}
+
+fn vals_offset<K, V, const B: usize, S>(cap: usize) -> usize {
+    let keys_end = keys_offset::<K, V, B, S>() + cap * mem::size_of::<K>();
+    round_up(keys_end, mem::align_of::<V>())
+}
+
+fn edges_offset<K, V, const B: usize, S>(cap: usize) -> usize {
+    let vals_end = vals_offset::<K, V, B, S>(cap) + cap * mem::size_of::<V>();
+    round_up(vals_end, mem::align_of::<NodePtr<K, V, B, S>>())
+}
+
+fn internal_offset<K, V, const B: usize, S>(cap: usize) -> usize {
+    let edges_end = edges_offset::<K, V, B, S>(cap) + (cap + 1) * mem::size_of::<NodePtr<K, V, B, S>>();
+    round_up(edges_end, mem::align_of::<InternalNode<S>>())
+}
+
+fn node_layout<K, V, const B: usize, S>(cap: usize, height: usize) -> Layout {
+    let layout = Layout::new::<LeafNode<K, V, B, S>>();
+    let (layout, _) = layout.extend(Layout::array::<K>(cap).unwrap()).unwrap();
+    let (mut layout, _) = layout.extend(Layout::array::<V>(cap).unwrap()).unwrap();
+    if height > 0 {
+        let edges = Layout::array::<NodePtr<K, V, B, S>>(cap + 1).unwrap();
+        (layout, _) = layout.extend(edges).unwrap();
+        (layout, _) = layout.extend(Layout::new::<InternalNode<S>>()).unwrap();
+    }
+    layout.pad_to_align()
+}
+
+unsafe fn alloc_node<K, V, const B: usize, S>(cap: usize, height: usize) -> NodePtr<K, V, B, S> {
+    let layout = node_layout::<K, V, B, S>(cap, height);
+    let node = match NonNull::new(alloc::alloc(layout)) {
+        Some(node) => node.cast::<LeafNode<K, V, B, S>>(),
+        None => alloc::handle_alloc_error(layout),
+    };
+    ptr::write(
+        node.as_ptr(),
+        LeafNode {
+            parent: None,
+            parent_idx: 0,
+            len: 0,
+            cap: cap as u32,
+            _marker: PhantomData,
+        },
+    );
+    if height > 0 {
+        ptr::write(internal(node), InternalNode { size: 0, summary: UnsafeCell::new(None) });
+    }
+    node
+}
+
+pub(crate) fn new_leaf<K, V, const B: usize, S>(cap: usize) -> NodePtr<K, V, B, S> {
+    unsafe { alloc_node(cap, 0) }
+}
+
+pub(crate) fn new_internal<K, V, const B: usize, S>(cap: usize) -> NodePtr<K, V, B, S> {
+    unsafe { alloc_node(cap, 1) }
+}
+
+// Frees the node allocation without touching the entries it holds.
+pub(crate) unsafe fn dealloc_node<K, V, const B: usize, S>(node: NodePtr<K, V, B, S>, height: usize) {
+    let cap = node_cap(node);
+    if height > 0 {
+        ptr::drop_in_place(internal(node));
+    }
+    alloc::dealloc(node.as_ptr().cast(), node_layout::<K, V, B, S>(cap, height));
+}
+
+// Slots in each node of a tree with the given order.
+pub(crate) fn node_slots<const B: usize>(order: usize) -> usize {
+    order.max(B) - 1
+}
+
+// The offset helpers below read a node's slot count only when it differs from
+// `B - 1`. Keeping that case out of line lets the common one fold to a
+// constant instead of waiting on the header load.
+#[cold]
+#[inline(never)]
+fn large_vals_offset<K, V, const B: usize, S>(cap: usize) -> usize {
+    vals_offset::<K, V, B, S>(cap)
+}
+
+#[cold]
+#[inline(never)]
+fn large_edges_offset<K, V, const B: usize, S>(cap: usize) -> usize {
+    edges_offset::<K, V, B, S>(cap)
+}
+
+#[cold]
+#[inline(never)]
+fn large_internal_offset<K, V, const B: usize, S>(cap: usize) -> usize {
+    internal_offset::<K, V, B, S>(cap)
+}
+
+unsafe fn internal<K, V, const B: usize, S>(node: NodePtr<K, V, B, S>) -> *mut InternalNode<S> {
+    let cap = node_cap(node);
+    let offset = if cap == B - 1 {
+        internal_offset::<K, V, B, S>(B - 1)
+    } else {
+        large_internal_offset::<K, V, B, S>(cap)
+    };
+    node.as_ptr().cast::<u8>().add(offset).cast()
+}
+
+pub(crate) unsafe fn len<K, V, const B: usize, S>(node: NodePtr<K, V, B, S>) -> usize {
+    (*node.as_ptr()).len as usize
+}
+
+unsafe fn set_len<K, V, const B: usize, S>(node: NodePtr<K, V, B, S>, len: usize) {
+    (*node.as_ptr()).len = len as u32;
+}
+
+unsafe fn node_cap<K, V, const B: usize, S>(node: NodePtr<K, V, B, S>) -> usize {
+    (*node.as_ptr()).cap as usize
+}
+
+pub(crate) unsafe fn key_ptr<K, V, const B: usize, S>(node: NodePtr<K, V, B, S>, idx: usize) -> *mut K {
+    node.as_ptr().cast::<u8>().add(keys_offset::<K, V, B, S>()).cast::<K>().add(idx)
+}
+
+pub(crate) unsafe fn val_ptr<K, V, const B: usize, S>(node: NodePtr<K, V, B, S>, idx: usize) -> *mut V {
+    let cap = node_cap(node);
+    let vals = if cap == B - 1 {
+        vals_offset::<K, V, B, S>(B - 1)
+    } else {
+        large_vals_offset::<K, V, B, S>(cap)
+    };
+    node.as_ptr().cast::<u8>().add(vals).cast::<V>().add(idx)
+}
+
+unsafe fn edge_ptr<K, V, const B: usize, S>(
+    node: NodePtr<K, V, B, S>,
+    idx: usize,
+) -> *mut NodePtr<K, V, B, S> {
+    let cap = node_cap(node);
+    let edges = if cap == B - 1 {
+        edges_offset::<K, V, B, S>(B - 1)
+    } else {
+        large_edges_offset::<K, V, B, S>(cap)
+    };
+    node.as_ptr().cast::<u8>().add(edges).cast::<NodePtr<K, V, B, S>>().add(idx)
+}
+
+pub(crate) unsafe fn edge<K, V, const B: usize, S>(
+    node: NodePtr<K, V, B, S>,
+    idx: usize,
+) -> NodePtr<K, V, B, S> {
+    *edge_ptr(node, idx)
+}
+
+pub(crate) unsafe fn subtree_len<K, V, const B: usize, S>(
+    node: NodePtr<K, V, B, S>,
+    height: usize,
+) -> usize {
+    if height == 0 {
+        len(node)
+    } else {
+        (*internal(node)).size
+    }
+}
+
+// Recomputes an internal node's subtree size and summary from its children.
+unsafe fn recount<K, V, const B: usize, S>(node: NodePtr<K, V, B, S>, height: usize)
+where
+    S: Summary<K, V>,
+{
+    debug_assert!(height > 0);
+    let len = len(node);
+    let mut size = len;
+    for idx in 0..=len {
+        size += subtree_len(edge(node, idx), height - 1);
+    }
+    (*internal(node)).size = size;
+    resummarize(node, height);
+}
+
+// Zero-sized summaries carry nothing, so their caches are never filled.
+unsafe fn resummarize<K, V, const B: usize, S>(node: NodePtr<K, V, B, S>, height: usize)
+where
+    S: Summary<K, V>,
+{
+    if mem::size_of::<S>() != 0 {
+        *(*internal(node)).summary.get() = None;
+        summarize(node, height);
+    }
+}
+
+// Recomputes the summaries of `node` and its ancestors after an entry in
+// `node` was replaced in place.
+pub(crate) unsafe fn resummarize_path<K, V, const B: usize, S>(
+    mut node: NodePtr<K, V, B, S>,
+    mut height: usize,
+)
+where
+    S: Summary<K, V>,
+{
+    if mem::size_of::<S>() == 0 {
+        return;
+    }
+    if height > 0 {
+        resummarize(node, height);
+    }
+    while let Some((parent, _)) = parent(node) {
+        node = parent;
+        height += 1;
+        resummarize(node, height);
+    }
+}
+
+unsafe fn recount_subtree<K, V, const B: usize, S>(node: NodePtr<K, V, B, S>, height: usize)
+where
+    S: Summary<K, V>,
+{
+    if height > 0 {
+        for idx in 0..=len(node) {
+            recount_subtree(edge(node, idx), height - 1);
+        }
+        recount(node, height);
+    }
+}
+
+unsafe fn recount_ancestors<K, V, const B: usize, S>(mut node: NodePtr<K, V, B, S>)
+where
+    S: Summary<K, V>,
+{
+    let mut height = 0;
+    while let Some((parent, _)) = parent(node) {
+        node = parent;
+        height += 1;
+        recount(node, height);
+    }
+}
+
+unsafe fn add_to_ancestors<K, V, const B: usize, S>(
+    mut node: NodePtr<K, V, B, S>,
+    mut height: usize,
+    amount: usize,
+)
+where
+    S: Summary<K, V>,
+{
+    while let Some((parent, _)) = parent(node) {
+        node = parent;
+        height += 1;
+        (*internal(node)).size += amount;
+        resummarize(node, height);
+    }
+}
+
+unsafe fn sub_from_ancestors<K, V, const B: usize, S>(
+    mut node: NodePtr<K, V, B, S>,
+    mut height: usize,
+    amount: usize,
+)
+where
+    S: Summary<K, V>,
+{
+    while let Some((parent, _)) = parent(node) {
+        node = parent;
+        height += 1;
+        (*internal(node)).size -= amount;
+        resummarize(node, height);
+    }
+}
+
+unsafe fn clear_summaries<K, V, const B: usize, S>(mut node: NodePtr<K, V, B, S>, height: usize) {
+    if height > 0 {
+        *(*internal(node)).summary.get() = None;
+    }
+    while let Some((parent, _)) = parent(node) {
+        *(*internal(parent)).summary.get() = None;
+        node = parent;
+    }
+}
+
+pub(crate) unsafe fn parent<K, V, const B: usize, S>(
+    node: NodePtr<K, V, B, S>,
+) -> Option<(NodePtr<K, V, B, S>, usize)> {
+    let leaf = &*node.as_ptr();
+    leaf.parent.map(|parent| (parent.cast(), leaf.parent_idx as usize))
+}
+
+unsafe fn correct_parent_link<K, V, const B: usize, S>(node: NodePtr<K, V, B, S>, idx: usize) {
+    let child = edge(node, idx);
+    (*child.as_ptr()).parent = Some(node.cast());
+    (*child.as_ptr()).parent_idx = idx as u32;
+}
+
+unsafe fn correct_parent_links<K, V, const B: usize, S>(
+    node: NodePtr<K, V, B, S>,
+    from: usize,
+    to: usize,
+) {
+    for idx in from..=to {
+        correct_parent_link(node, idx);
+    }
+}
+
+unsafe fn slice_insert<T>(base: *mut T, len: usize, idx: usize, value: T) {
+    if idx < len {
+        ptr::copy(base.add(idx), base.add(idx + 1), len - idx);
+    }
+    ptr::write(base.add(idx), value);
+}
+
+unsafe fn slice_remove<T>(base: *mut T, len: usize, idx: usize) -> T {
+    let value = ptr::read(base.add(idx));
+    if idx + 1 < len {
+        ptr::copy(base.add(idx + 1), base.add(idx), len - idx - 1);
+    }
+    value
+}
+
+// A position inside a node: an entry when `idx < len`, or the gap (edge)
+// at `idx` when used for navigation between entries.
+pub(crate) struct Handle<K, V, const B: usize, S> {
+    pub(crate) node: NodePtr<K, V, B, S>,
+    pub(crate) height: usize,
+    pub(crate) idx: usize,
+}
+
+impl<K, V, const B: usize, S> Clone for Handle<K, V, B, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V, const B: usize, S> Copy for Handle<K, V, B, S> {}
+
+impl<K, V, const B: usize, S> PartialEq for Handle<K, V, B, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node && self.idx == other.idx
+    }
+}
+
+impl<K, V, const B: usize, S> Handle<K, V, B, S> {
+    pub(crate) fn new(node: NodePtr<K, V, B, S>, height: usize, idx: usize) -> Self {
+        Handle { node, height, idx }
+    }
+
+    pub(crate) unsafe fn key<'a>(self) -> &'a K {
+        &*key_ptr(self.node, self.idx)
+    }
+
+    pub(crate) unsafe fn val<'a>(self) -> &'a V {
+        &*val_ptr(self.node, self.idx)
+    }
+
+    // The caller may change the value, so every summary covering it is marked
+    // stale. Zero-sized summaries never change and skip the walk.
+    pub(crate) unsafe fn val_mut<'a>(self) -> &'a mut V {
+        if mem::size_of::<S>() != 0 {
+            clear_summaries(self.node, self.height);
+        }
+        &mut *val_ptr(self.node, self.idx)
+    }
+
+    // Entry at or after this edge, climbing to an ancestor when the edge is
+    // the last one in its node.
+    pub(crate) unsafe fn next_kv(self) -> Option<Self> {
+        let mut handle = self;
+        loop {
+            if handle.idx < len(handle.node) {
+                return Some(handle);
+            }
+            let (parent, parent_idx) = parent(handle.node)?;
+            handle = Handle::new(parent, handle.height + 1, parent_idx);
+        }
+    }
+
+    pub(crate) unsafe fn prev_kv(self) -> Option<Self> {
+        let mut handle = self;
+        loop {
+            if handle.idx > 0 {
+                return Some(Handle::new(handle.node, handle.height, handle.idx - 1));
+            }
+            let (parent, parent_idx) = parent(handle.node)?;
+            handle = Handle::new(parent, handle.height + 1, parent_idx);
+        }
+    }
+
+    // Leaf edge immediately to the right of this entry.
+    pub(crate) unsafe fn right_leaf_edge(self) -> Self {
+        if self.height == 0 {
+            Handle::new(self.node, 0, self.idx + 1)
+        } else {
+            first_leaf_edge(edge(self.node, self.idx + 1), self.height - 1)
+        }
+    }
+
+    pub(crate) unsafe fn left_leaf_edge(self) -> Self {
+        if self.height == 0 {
+            Handle::new(self.node, 0, self.idx)
+        } else {
+            last_leaf_edge(edge(self.node, self.idx), self.height - 1)
+        }
+    }
+}
+
+pub(crate) unsafe fn first_leaf_edge<K, V, const B: usize, S>(
+    mut node: NodePtr<K, V, B, S>,
+    mut height: usize,
+) -> Handle<K, V, B, S> {
+    while height > 0 {
+        node = edge(node, 0);
+        height -= 1;
+    }
+    Handle::new(node, 0, 0)
+}
+
+pub(crate) unsafe fn last_leaf_edge<K, V, const B: usize, S>(
+    mut node: NodePtr<K, V, B, S>,
+    mut height: usize,
+) -> Handle<K, V, B, S> {
+    while height > 0 {
+        node = edge(node, len(node));
+        height -= 1;
+    }
+    Handle::new(node, 0, len(node))
+}
+
+// Finds `Ok(idx)` of the matching entry or `Err(idx)` of the edge to follow.
+// `cmp` orders the searched-for key against each stored key.
+pub(crate) unsafe fn search_node<K, V, const B: usize, S, F>(
+    node: NodePtr<K, V, B, S>,
+    cmp: &mut F,
+) -> Result<usize, usize>
+where
+    F: FnMut(&K) -> Ordering,
+{
+    let len = len(node);
+    for idx in 0..len {
+        match cmp(&*key_ptr(node, idx)) {
+            Ordering::Greater => {}
+            Ordering::Equal => return Ok(idx),
+            Ordering::Less => return Err(idx),
+        }
+    }
+    Err(len)
+}
+
+pub(crate) enum SearchResult<K, V, const B: usize, S> {
+    Found(Handle<K, V, B, S>),
+    GoDown(Handle<K, V, B, S>),
+}
+
+pub(crate) unsafe fn search_tree<K, V, const B: usize, S, F>(
+    mut node: NodePtr<K, V, B, S>,
+    mut height: usize,
+    mut cmp: F,
+) -> SearchResult<K, V, B, S>
+where
+    F: FnMut(&K) -> Ordering,
+{
+    loop {
+        match search_node(node, &mut cmp) {
+            Ok(idx) => return SearchResult::Found(Handle::new(node, height, idx)),
+            Err(idx) => {
+                if height == 0 {
+                    return SearchResult::GoDown(Handle::new(node, 0, idx));
+                }
+                node = edge(node, idx);
+                height -= 1;
+            }
+        }
+    }
+}
+
+// Descends to the leaf edge with exactly the keys matching `before` on its
+// left. `before` must hold for a prefix of the keys in sorted order.
+pub(crate) unsafe fn partition_leaf_edge<K, V, const B: usize, S, F>(
+    mut node: NodePtr<K, V, B, S>,
+    mut height: usize,
+    mut before: F,
+) -> Handle<K, V, B, S>
+where
+    F: FnMut(&K) -> bool,
+{
+    loop {
+        let idx = count_prefix(node, &mut before);
+        if height == 0 {
+            return Handle::new(node, 0, idx);
+        }
+        node = edge(node, idx);
+        height -= 1;
+    }
+}
+
+// Moves a leaf edge forward past every entry matching `before`, which must
+// already hold for everything before `edge`. Climbs only until an ancestor
+// ends past the target, so short moves stay near the leaves.
+pub(crate) unsafe fn seek_leaf_edge<K, V, const B: usize, S, F>(
+    edge: Handle<K, V, B, S>,
+    mut before: F,
+) -> Handle<K, V, B, S>
+where
+    F: FnMut(&K) -> bool,
+{
+    debug_assert_eq!(edge.height, 0);
+    let mut node = edge.node;
+    let mut height = 0;
+    loop {
+        let len = len(node);
+        if len > 0 && !before(&*key_ptr(node, len - 1)) {
+            break;
+        }
+        match parent(node) {
+            Some((parent, _)) => {
+                node = parent;
+                height += 1;
+            }
+            None => break,
+        }
+    }
+    partition_leaf_edge(node, height, before)
+}
+
+// Counts the entries matching `before`, which must hold for a prefix of the
+// keys in sorted order.
+pub(crate) unsafe fn rank<K, V, const B: usize, S, F>(
+    mut node: NodePtr<K, V, B, S>,
+    mut height: usize,
+    mut before: F,
+) -> usize
+where
+    F: FnMut(&K) -> bool,
+{
+    let mut rank = 0;
+    loop {
+        let idx = count_prefix(node, &mut before);
+        rank += idx;
+        if height == 0 {
+            return rank;
+        }
+        for child in 0..idx {
+            rank += subtree_len(edge(node, child), height - 1);
+        }
+        node = edge(node, idx);
+        height -= 1;
+    }
+}
+
+// Entry at `index` in sorted order; `index` must be below the subtree size.
+pub(crate) unsafe fn select<K, V, const B: usize, S>(
+    mut node: NodePtr<K, V, B, S>,
+    mut height: usize,
+    mut index: usize,
+) -> Handle<K, V, B, S> {
+    'descend: loop {
+        if height == 0 {
+            return Handle::new(node, 0, index);
+        }
+        for idx in 0..=len(node) {
+            let size = subtree_len(edge(node, idx), height - 1);
+            if index < size {
+                node = edge(node, idx);
+                height -= 1;
+                continue 'descend;
+            }
+            if index == size {
+                return Handle::new(node, height, idx);
+            }
+            index -= size + 1;
+        }
+        unreachable!("index beyond subtree size");
+    }
+}
+
+unsafe fn count_prefix<K, V, const B: usize, S, F>(node: NodePtr<K, V, B, S>, pred: &mut F) -> usize
+where
+    F: FnMut(&K) -> bool,
+{
+    let len = len(node);
+    let mut idx = 0;
+    while idx < len && pred(&*key_ptr(node, idx)) {
+        idx += 1;
+    }
+    idx
+}
+
+unsafe fn summarize_entries<K, V, const B: usize, S>(
+    node: NodePtr<K, V, B, S>,
+    from: usize,
+    to: usize,
+) -> S
+where
+    S: Summary<K, V>,
+{
+    let mut acc = S::identity();
+    for idx in from..to {
+        acc = acc.combine(&S::from_entry(&*key_ptr(node, idx), &*val_ptr(node, idx)));
+    }
+    acc
+}
+
+// Summary of a whole subtree, filling in stale caches on the way.
+pub(crate) unsafe fn summarize<K, V, const B: usize, S>(node: NodePtr<K, V, B, S>, height: usize) -> S
+where
+    S: Summary<K, V>,
+{
+    if height == 0 {
+        return summarize_entries(node, 0, len(node));
+    }
+    let internal = internal(node);
+    if let Some(summary) = &*(*internal).summary.get() {
+        return summary.clone();
+    }
+    let mut acc = summarize(edge(node, 0), height - 1);
+    for idx in 0..len(node) {
+        acc = acc.combine(&S::from_entry(&*key_ptr(node, idx), &*val_ptr(node, idx)));
+        acc = acc.combine(&summarize(edge(node, idx + 1), height - 1));
+    }
+    *(*internal).summary.get() = Some(acc.clone());
+    acc
+}
+
+// Summary of the entries for which `before_start` fails.
+unsafe fn summarize_suffix<K, V, const B: usize, S, F>(
+    node: NodePtr<K, V, B, S>,
+    height: usize,
+    before_start: &mut F,
+) -> S
+where
+    S: Summary<K, V>,
+    F: FnMut(&K) -> bool,
+{
+    let start = count_prefix(node, before_start);
+    if height == 0 {
+        return summarize_entries(node, start, len(node));
+    }
+    let mut acc = summarize_suffix(edge(node, start), height - 1, before_start);
+    for idx in start..len(node) {
+        acc = acc.combine(&S::from_entry(&*key_ptr(node, idx), &*val_ptr(node, idx)));
+        acc = acc.combine(&summarize(edge(node, idx + 1), height - 1));
+    }
+    acc
+}
+
+// Summary of the entries for which `before_end` holds.
+unsafe fn summarize_prefix<K, V, const B: usize, S, F>(
+    node: NodePtr<K, V, B, S>,
+    height: usize,
+    before_end: &mut F,
+) -> S
+where
+    S: Summary<K, V>,
+    F: FnMut(&K) -> bool,
+{
+    let end = count_prefix(node, before_end);
+    if height == 0 {
+        return summarize_entries(node, 0, end);
+    }
+    let mut acc = S::identity();
+    for idx in 0..end {
+        acc = acc.combine(&summarize(edge(node, idx), height - 1));
+        acc = acc.combine(&S::from_entry(&*key_ptr(node, idx), &*val_ptr(node, idx)));
+    }
+    acc.combine(&summarize_prefix(edge(node, end), height - 1, before_end))
+}
+
+// Summary of the entries between the prefix matching `before_start` and the
+// prefix matching `before_end`, which must be at least as long.
+pub(crate) unsafe fn summarize_range<K, V, const B: usize, S, F, G>(
+    mut node: NodePtr<K, V, B, S>,
+    mut height: usize,
+    mut before_start: F,
+    mut before_end: G,
+) -> S
+where
+    S: Summary<K, V>,
+    F: FnMut(&K) -> bool,
+    G: FnMut(&K) -> bool,
+{
+    loop {
+        let start = count_prefix(node, &mut before_start);
+        let end = count_prefix(node, &mut before_end);
+        if height == 0 {
+            return summarize_entries(node, start, end);
+        }
+        if start == end {
+            node = edge(node, start);
+            height -= 1;
+            continue;
+        }
+        let mut acc = summarize_suffix(edge(node, start), height - 1, &mut before_start);
+        for idx in start..end {
+            acc = acc.combine(&S::from_entry(&*key_ptr(node, idx), &*val_ptr(node, idx)));
+            if idx + 1 < end {
+                acc = acc.combine(&summarize(edge(node, idx + 1), height - 1));
+            }
+        }
+        return acc.combine(&summarize_prefix(edge(node, end), height - 1, &mut before_end));
+    }
+}
+
+pub(crate) struct Root<K, V, const B: usize, S> {
+    pub(crate) node: NodePtr<K, V, B, S>,
+    pub(crate) height: usize,
+}
+
+impl<K, V, const B: usize, S> Root<K, V, B, S> {
+    pub(crate) fn new_leaf(order: usize) -> Self {
+        Root {
+            node: new_leaf(node_slots::<B>(order)),
+            height: 0,
+        }
+    }
+
+    pub(crate) unsafe fn push_internal_level(&mut self) {
+        let node = new_internal::<K, V, B, S>(node_cap(self.node));
+        *edge_ptr(node, 0) = self.node;
+        correct_parent_link(node, 0);
+        (*internal(node)).size = subtree_len(self.node, self.height);
+        self.node = node;
+        self.height += 1;
+    }
+
+    pub(crate) unsafe fn pop_internal_level(&mut self) {
+        debug_assert!(self.height > 0);
+        let top = self.node;
+        self.node = edge(top, 0);
+        (*self.node.as_ptr()).parent = None;
+        self.height -= 1;
+        dealloc_node(top, self.height + 1);
+    }
+
+    pub(crate) unsafe fn first_leaf_edge(&self) -> Handle<K, V, B, S> {
+        first_leaf_edge(self.node, self.height)
+    }
+
+    pub(crate) unsafe fn last_leaf_edge(&self) -> Handle<K, V, B, S> {
+        last_leaf_edge(self.node, self.height)
+    }
+
+    // Drops every entry and frees every node.
+    pub(crate) unsafe fn drop_all(self) {
+        drop_subtree(self.node, self.height);
+    }
+
+    // Frees every node; the entries must already have been moved out.
+    pub(crate) unsafe fn dealloc_all(self) {
+        dealloc_subtree(self.node, self.height);
+    }
+}
+
+unsafe fn drop_subtree<K, V, const B: usize, S>(node: NodePtr<K, V, B, S>, height: usize) {
+    let len = len(node);
+    for idx in 0..len {
+        ptr::drop_in_place(key_ptr(node, idx));
+        ptr::drop_in_place(val_ptr(node, idx));
+    }
+    if height > 0 {
+        for idx in 0..=len {
+            drop_subtree(edge(node, idx), height - 1);
+        }
+    }
+    dealloc_node(node, height);
+}
+
+unsafe fn dealloc_subtree<K, V, const B: usize, S>(node: NodePtr<K, V, B, S>, height: usize) {
+    if height > 0 {
+        for idx in 0..=len(node) {
+            dealloc_subtree(edge(node, idx), height - 1);
+        }
+    }
+    dealloc_node(node, height);
+}
+
+fn capacity(order: usize) -> usize {
+    order - 1
+}
+
+fn min_len(order: usize) -> usize {
+    (order - 1) / 2
+}
+
+// Inserts into a node with room to spare. For internal nodes `right` is the
+// child that follows the new entry.
+unsafe fn insert_fit<K, V, const B: usize, S>(
+    node: NodePtr<K, V, B, S>,
+    height: usize,
+    idx: usize,
+    key: K,
+    val: V,
+    right: Option<NodePtr<K, V, B, S>>,
+) {
+    let len = len(node);
+    debug_assert!(len < node_cap(node));
+    slice_insert(key_ptr(node, 0), len, idx, key);
+    slice_insert(val_ptr(node, 0), len, idx, val);
+    if height > 0 {
+        slice_insert(edge_ptr(node, 0), len + 1, idx + 1, right.unwrap());
+        set_len(node, len + 1);
+        correct_parent_links(node, idx + 1, len + 1);
+    } else {
+        set_len(node, len + 1);
+    }
+}
+
+// Moves entries `at..len` (and the edges after them) into a fresh sibling.
+unsafe fn split_suffix<K, V, const B: usize, S>(
+    node: NodePtr<K, V, B, S>,
+    height: usize,
+    at: usize,
+) -> NodePtr<K, V, B, S> {
+    let len = len(node);
+    let count = len - at;
+    let cap = node_cap(node);
+    let right = if height == 0 { new_leaf(cap) } else { new_internal(cap) };
+    ptr::copy_nonoverlapping(key_ptr(node, at), key_ptr(right, 0), count);
+    ptr::copy_nonoverlapping(val_ptr(node, at), val_ptr(right, 0), count);
+    set_len(node, at);
+    set_len(right, count);
+    if height > 0 {
+        ptr::copy_nonoverlapping(edge_ptr(node, at + 1), edge_ptr(right, 1), count);
+        correct_parent_links(right, 1, count);
+    }
+    right
+}
+
+struct Split<K, V, const B: usize, S> {
+    key: K,
+    val: V,
+    right: NodePtr<K, V, B, S>,
+}
+
+// Splits a full node while inserting one more entry, so the two halves end up
+// with `order / 2` and `(order - 1) / 2` entries. Returns where the new entry
+// landed, or `None` when it became the separator that moves up.
+#[allow(clippy::type_complexity)]
+unsafe fn split_insert<K, V, const B: usize, S>(
+    node: NodePtr<K, V, B, S>,
+    height: usize,
+    idx: usize,
+    key: K,
+    val: V,
+    edge: Option<NodePtr<K, V, B, S>>,
+    order: usize,
+) -> (Option<Handle<K, V, B, S>>, Split<K, V, B, S>)
+where
+    S: Summary<K, V>,
+{
+    let middle = order / 2;
+    if idx == middle {
+        let right = split_suffix(node, height, middle);
+        if height > 0 {
+            let edge = edge.unwrap();
+            *edge_ptr(right, 0) = edge;
+            correct_parent_link(right, 0);
+            recount(node, height);
+            recount(right, height);
+        }
+        return (None, Split { key, val, right });
+    }
+
+    let at = if idx < middle { middle - 1 } else { middle };
+    let right = split_suffix(node, height, at + 1);
+    let len = len(node);
+    let mid_key = ptr::read(key_ptr(node, len - 1));
+    let mid_val = ptr::read(val_ptr(node, len - 1));
+    set_len(node, len - 1);
+    if height > 0 {
+        *edge_ptr(right, 0) = edge_ptr(node, len).read();
+        correct_parent_link(right, 0);
+    }
+
+    let landed = if idx < middle {
+        insert_fit(node, height, idx, key, val, edge);
+        Handle::new(node, height, idx)
+    } else {
+        let idx = idx - middle - 1;
+        insert_fit(right, height, idx, key, val, edge);
+        Handle::new(right, height, idx)
+    };
+    if height > 0 {
+        recount(node, height);
+        recount(right, height);
+    }
+    (
+        Some(landed),
+        Split {
+            key: mid_key,
+            val: mid_val,
+            right,
+        },
+    )
+}
+
+// Inserts at a leaf edge, splitting full nodes on the way back up to the root.
+// Returns the handle of the inserted entry.
+pub(crate) unsafe fn insert_recursing<K, V, const B: usize, S>(
+    edge: Handle<K, V, B, S>,
+    key: K,
+    val: V,
+    order: usize,
+    root: &mut Root<K, V, B, S>,
+) -> Handle<K, V, B, S>
+where
+    S: Summary<K, V>,
+{
+    debug_assert!(edge.height == 0);
+    insert_into(edge.node, 0, edge.idx, key, val, None, 1, order, root)
+}
+
+// Inserts an entry (and, above the leaves, the child to its right) at `idx`,
+// splitting upward as needed. `added` is how many entries the tree gains.
+#[allow(clippy::too_many_arguments)]
+unsafe fn insert_into<K, V, const B: usize, S>(
+    mut node: NodePtr<K, V, B, S>,
+    mut height: usize,
+    mut idx: usize,
+    mut key: K,
+    mut val: V,
+    mut right: Option<NodePtr<K, V, B, S>>,
+    added: usize,
+    order: usize,
+    root: &mut Root<K, V, B, S>,
+) -> Handle<K, V, B, S>
+where
+    S: Summary<K, V>,
+{
+    let mut landed = None;
+
+    loop {
+        if len(node) < capacity(order) {
+            insert_fit(node, height, idx, key, val, right);
+            if height > 0 {
+                recount(node, height);
+            }
+            add_to_ancestors(node, height, added);
+            return landed.unwrap_or(Handle::new(node, height, idx));
+        }
+
+        let (position, split) = split_insert(node, height, idx, key, val, right, order);
+        if landed.is_none() {
+            landed = position;
+        }
+        key = split.key;
+        val = split.val;
+        right = Some(split.right);
+
+        match parent(node) {
+            Some((parent, parent_idx)) => {
+                node = parent;
+                idx = parent_idx;
+                height += 1;
+            }
+            None => {
+                root.push_internal_level();
+                insert_fit(root.node, root.height, 0, key, val, right);
+                recount(root.node, root.height);
+                return landed.unwrap_or(Handle::new(root.node, root.height, 0));
+            }
+        }
+    }
+}
+
+// Moves `count` entries from the left child of separator `idx` to its right
+// child, rotating them through the separator.
+unsafe fn bulk_steal_left<K, V, const B: usize, S>(
+    parent: NodePtr<K, V, B, S>,
+    idx: usize,
+    height: usize,
+    count: usize,
+)
+where
+    S: Summary<K, V>,
+{
+    let left = edge(parent, idx);
+    let right = edge(parent, idx + 1);
+    let left_len = len(left);
+    let right_len = len(right);
+    debug_assert!(count > 0 && count <= left_len);
+
+    ptr::copy(key_ptr(right, 0), key_ptr(right, count), right_len);
+    ptr::copy(val_ptr(right, 0), val_ptr(right, count), right_len);
+
+    let from = left_len - count;
+    ptr::copy_nonoverlapping(key_ptr(left, from + 1), key_ptr(right, 0), count - 1);
+    ptr::copy_nonoverlapping(val_ptr(left, from + 1), val_ptr(right, 0), count - 1);
+
+    let parent_key = ptr::replace(key_ptr(parent, idx), ptr::read(key_ptr(left, from)));
+    let parent_val = ptr::replace(val_ptr(parent, idx), ptr::read(val_ptr(left, from)));
+    ptr::write(key_ptr(right, count - 1), parent_key);
+    ptr::write(val_ptr(right, count - 1), parent_val);
+
+    if height > 0 {
+        ptr::copy(edge_ptr(right, 0), edge_ptr(right, count), right_len + 1);
+        ptr::copy_nonoverlapping(edge_ptr(left, from + 1), edge_ptr(right, 0), count);
+        correct_parent_links(right, 0, right_len + count);
+    }
+    set_len(left, from);
+    set_len(right, right_len + count);
+    if height > 0 {
+        recount(left, height);
+        recount(right, height);
+    }
+}
+
+unsafe fn bulk_steal_right<K, V, const B: usize, S>(
+    parent: NodePtr<K, V, B, S>,
+    idx: usize,
+    height: usize,
+    count: usize,
+)
+where
+    S: Summary<K, V>,
+{
+    let left = edge(parent, idx);
+    let right = edge(parent, idx + 1);
+    let left_len = len(left);
+    let right_len = len(right);
+    debug_assert!(count > 0 && count <= right_len);
+
+    let parent_key = ptr::replace(key_ptr(parent, idx), ptr::read(key_ptr(right, count - 1)));
+    let parent_val = ptr::replace(val_ptr(parent, idx), ptr::read(val_ptr(right, count - 1)));
+    ptr::write(key_ptr(left, left_len), parent_key);
+    ptr::write(val_ptr(left, left_len), parent_val);
+
+    ptr::copy_nonoverlapping(key_ptr(right, 0), key_ptr(left, left_len + 1), count - 1);
+    ptr::copy_nonoverlapping(val_ptr(right, 0), val_ptr(left, left_len + 1), count - 1);
+    ptr::copy(key_ptr(right, count), key_ptr(right, 0), right_len - count);
+    ptr::copy(val_ptr(right, count), val_ptr(right, 0), right_len - count);
+
+    if height > 0 {
+        ptr::copy_nonoverlapping(edge_ptr(right, 0), edge_ptr(left, left_len + 1), count);
+        ptr::copy(edge_ptr(right, count), edge_ptr(right, 0), right_len - count + 1);
+        correct_parent_links(left, left_len + 1, left_len + count);
+        correct_parent_links(right, 0, right_len - count);
+    }
+    set_len(left, left_len + count);
+    set_len(right, right_len - count);
+    if height > 0 {
+        recount(left, height);
+        recount(right, height);
+    }
+}
+
+// Folds separator `idx` and the right child into the left child, then frees
+// the right child. Returns the merged node.
+unsafe fn merge<K, V, const B: usize, S>(
+    parent: NodePtr<K, V, B, S>,
+    idx: usize,
+    height: usize,
+) -> NodePtr<K, V, B, S>
+where
+    S: Summary<K, V>,
+{
+    let left = edge(parent, idx);
+    let right = edge(parent, idx + 1);
+    let left_len = len(left);
+    let right_len = len(right);
+    let parent_len = len(parent);
+
+    ptr::write(key_ptr(left, left_len), slice_remove(key_ptr(parent, 0), parent_len, idx));
+    ptr::write(val_ptr(left, left_len), slice_remove(val_ptr(parent, 0), parent_len, idx));
+    ptr::copy_nonoverlapping(key_ptr(right, 0), key_ptr(left, left_len + 1), right_len);
+    ptr::copy_nonoverlapping(val_ptr(right, 0), val_ptr(left, left_len + 1), right_len);
+
+    slice_remove(edge_ptr(parent, 0), parent_len + 1, idx + 1);
+    set_len(parent, parent_len - 1);
+    if idx + 1 < parent_len {
+        correct_parent_links(parent, idx + 1, parent_len - 1);
+    }
+
+    if height > 0 {
+        ptr::copy_nonoverlapping(edge_ptr(right, 0), edge_ptr(left, left_len + 1), right_len + 1);
+        correct_parent_links(left, left_len + 1, left_len + right_len + 1);
+    }
+    set_len(left, left_len + right_len + 1);
+    if height > 0 {
+        recount(left, height);
+    }
+    dealloc_node(right, height);
+    left
+}
+
+// Restores the minimum occupancy of child `idx` of `parent` by merging it with
+// a sibling when they fit together, or stealing from that sibling otherwise.
+// `tracked` follows a leaf edge through the move. Returns true on a merge,
+// which takes an entry away from `parent`.
+unsafe fn fix_child<K, V, const B: usize, S>(
+    parent: NodePtr<K, V, B, S>,
+    idx: usize,
+    height: usize,
+    order: usize,
+    tracked: Option<&mut Handle<K, V, B, S>>,
+) -> bool
+where
+    S: Summary<K, V>,
+{
+    let child = edge(parent, idx);
+    let child_len = len(child);
+    let needed = min_len(order) - child_len;
+
+    if idx > 0 {
+        let left = edge(parent, idx - 1);
+        let left_len = len(left);
+        if left_len + child_len < capacity(order) {
+            merge(parent, idx - 1, height);
+            if let Some(handle) = tracked {
+                *handle = Handle::new(left, height, left_len + 1 + handle.idx);
+            }
+            true
+        } else {
+            bulk_steal_left(parent, idx - 1, height, needed);
+            if let Some(handle) = tracked {
+                handle.idx += needed;
+            }
+            false
+        }
+    } else {
+        let right = edge(parent, 1);
+        if child_len + len(right) < capacity(order) {
+            merge(parent, 0, height);
+            true
+        } else {
+            bulk_steal_right(parent, 0, height, needed);
+            false
+        }
+    }
+}
+
+// Walks up from `node` restoring minimum occupancy, then drops an emptied
+// internal root.
+unsafe fn fix_upward<K, V, const B: usize, S>(
+    mut node: NodePtr<K, V, B, S>,
+    mut height: usize,
+    order: usize,
+    mut tracked: Option<&mut Handle<K, V, B, S>>,
+    root: &mut Root<K, V, B, S>,
+)
+where
+    S: Summary<K, V>,
+{
+    while len(node) < min_len(order) {
+        let Some((parent, parent_idx)) = parent(node) else {
+            break;
+        };
+        if !fix_child(parent, parent_idx, height, order, tracked.take()) {
+            break;
+        }
+        node = parent;
+        height += 1;
+    }
+    if root.height > 0 && len(root.node) == 0 {
+        root.pop_internal_level();
+    }
+}
+
+unsafe fn remove_leaf_kv<K, V, const B: usize, S>(
+    kv: Handle<K, V, B, S>,
+    order: usize,
+    root: &mut Root<K, V, B, S>,
+) -> ((K, V), Handle<K, V, B, S>)
+where
+    S: Summary<K, V>,
+{
+    debug_assert!(kv.height == 0);
+    let len = len(kv.node);
+    let key = slice_remove(key_ptr(kv.node, 0), len, kv.idx);
+    let val = slice_remove(val_ptr(kv.node, 0), len, kv.idx);
+    set_len(kv.node, len - 1);
+    sub_from_ancestors(kv.node, 0, 1);
+
+    let mut position = Handle::new(kv.node, 0, kv.idx);
+    fix_upward(kv.node, 0, order, Some(&mut position), root);
+    ((key, val), position)
+}
+
+// Removes an entry and rebalances. Returns it together with the leaf edge
+// where it used to sit, so iteration can carry on from there.
+pub(crate) unsafe fn remove_kv_tracking<K, V, const B: usize, S>(
+    kv: Handle<K, V, B, S>,
+    order: usize,
+    root: &mut Root<K, V, B, S>,
+) -> ((K, V), Handle<K, V, B, S>)
+where
+    S: Summary<K, V>,
+{
+    if kv.height == 0 {
+        return remove_leaf_kv(kv, order, root);
+    }
+
+    // Swap in the in-order predecessor, which always lives in a leaf.
+    let predecessor = kv.left_leaf_edge().prev_kv().unwrap();
+    let ((key, val), position) = remove_leaf_kv(predecessor, order, root);
+    let internal = position.next_kv().unwrap();
+    let old_key = ptr::replace(key_ptr(internal.node, internal.idx), key);
+    let old_val = ptr::replace(val_ptr(internal.node, internal.idx), val);
+    resummarize_path(internal.node, internal.height);
+    ((old_key, old_val), internal.right_leaf_edge())
+}
+
+// Drops empty levels from the top, keeping an empty leaf for an empty tree.
+unsafe fn fix_top<K, V, const B: usize, S>(root: &mut Root<K, V, B, S>) {
+    while root.height > 0 && len(root.node) == 0 {
+        root.pop_internal_level();
+    }
+}
+
+// Restores minimum occupancy along the right (or left) border of a tree whose
+// other nodes are already valid, working top-down. Border children are
+// stocked to one above the minimum where the sibling allows it, so a merge on
+// the next level down rarely leaves them short; when it does, start over.
+unsafe fn fix_border<K, V, const B: usize, S>(root: &mut Root<K, V, B, S>, order: usize, right: bool)
+where
+    S: Summary<K, V>,
+{
+    let min = min_len(order);
+    'restart: loop {
+        fix_top(root);
+        let mut node = root.node;
+        let mut height = root.height;
+        while height > 0 {
+            let node_len = len(node);
+            let (child_idx, sibling_idx) = if right { (node_len, node_len - 1) } else { (0, 1) };
+            let child = edge(node, child_idx);
+            let child_len = len(child);
+            let sibling_len = len(edge(node, sibling_idx));
+
+            if child_len <= min {
+                if child_len + sibling_len < capacity(order) {
+                    let merged = merge(node, child_idx.min(sibling_idx), height - 1);
+                    let underfull = match parent(node) {
+                        Some(_) => len(node) < min,
+                        None => len(node) == 0,
+                    };
+                    if underfull {
+                        continue 'restart;
+                    }
+                    node = merged;
+                    height -= 1;
+                    continue;
+                }
+                let count = (min + 1 - child_len).min(sibling_len - min);
+                if count > 0 {
+                    if right {
+                        bulk_steal_left(node, sibling_idx, height - 1, count);
+                    } else {
+                        bulk_steal_right(node, 0, height - 1, count);
+                    }
+                }
+            }
+            node = child;
+            height -= 1;
+        }
+        return;
+    }
+}
+
+// Appends ascending entries after everything already in the tree, filling
+// each node with `fill` entries before opening the next one. Returns the
+// number of entries pushed.
+pub(crate) unsafe fn bulk_push<K, V, const B: usize, S, I>(
+    root: &mut Root<K, V, B, S>,
+    iter: I,
+    fill: usize,
+    order: usize,
+) -> usize
+where
+    S: Summary<K, V>,
+    I: Iterator<Item = (K, V)>,
+{
+    let mut leaf = root.last_leaf_edge().node;
+    let mut count = 0;
+    for (key, val) in iter {
+        if len(leaf) < fill {
+            insert_fit(leaf, 0, len(leaf), key, val, None);
+        } else {
+            let mut open = leaf;
+            let mut height = 0;
+            loop {
+                match parent(open) {
+                    Some((parent, _)) => {
+                        open = parent;
+                        height += 1;
+                        if len(open) < fill {
+                            break;
+                        }
+                    }
+                    None => {
+                        root.push_internal_level();
+                        open = root.node;
+                        height = root.height;
+                        break;
+                    }
+                }
+            }
+
+            let mut subtree = new_leaf::<K, V, B, S>(node_slots::<B>(order));
+            for _ in 1..height {
+                let node = new_internal::<K, V, B, S>(node_slots::<B>(order));
+                *edge_ptr(node, 0) = subtree;
+                correct_parent_link(node, 0);
+                subtree = node;
+            }
+            insert_fit(open, height, len(open), key, val, Some(subtree));
+            leaf = last_leaf_edge(open, height).node;
+        }
+        count += 1;
+    }
+    recount_subtree(root.node, root.height);
+    fix_border(root, order, true);
+    count
+}
+
+// Moves every entry for which `before` fails into a new tree, leaving the
+// rest behind. Only the nodes on one root-to-leaf path are cut.
+pub(crate) unsafe fn split_root<K, V, const B: usize, S, F>(
+    root: &mut Root<K, V, B, S>,
+    order: usize,
+    mut before: F,
+) -> Root<K, V, B, S>
+where
+    S: Summary<K, V>,
+    F: FnMut(&K) -> bool,
+{
+    let mut node = root.node;
+    let mut height = root.height;
+    let mut right_root = None;
+    let mut right_parent = None;
+    let right_leaf = loop {
+        let len = len(node);
+        let mut idx = 0;
+        while idx < len && before(&*key_ptr(node, idx)) {
+            idx += 1;
+        }
+        let right = split_suffix(node, height, idx);
+        match right_parent {
+            None => right_root = Some(Root { node: right, height }),
+            Some(parent) => {
+                *edge_ptr(parent, 0) = right;
+                correct_parent_link(parent, 0);
+            }
+        }
+        if height == 0 {
+            break right;
+        }
+        node = edge(node, idx);
+        right_parent = Some(right);
+        height -= 1;
+    };
+    recount_ancestors(node);
+    recount_ancestors(right_leaf);
+
+    let mut right_root = right_root.unwrap();
+    fix_border(root, order, true);
+    fix_border(&mut right_root, order, false);
+    right_root
+}
+
+// Joins two non-empty trees around a separator entry. Every key in `left`
+// sorts before the separator and every key in `right` after it.
+pub(crate) unsafe fn join<K, V, const B: usize, S>(
+    mut left: Root<K, V, B, S>,
+    key: K,
+    val: V,
+    mut right: Root<K, V, B, S>,
+    order: usize,
+) -> Root<K, V, B, S>
+where
+    S: Summary<K, V>,
+{
+    let min = min_len(order);
+    if left.height == right.height {
+        let height = left.height;
+        left.push_internal_level();
+        insert_fit(left.node, left.height, 0, key, val, Some(right.node));
+        recount(left.node, left.height);
+
+        let left_len = len(edge(left.node, 0));
+        let right_len = len(right.node);
+        if left_len + right_len < capacity(order) {
+            merge(left.node, 0, height);
+            fix_top(&mut left);
+        } else if left_len < min {
+            bulk_steal_right(left.node, 0, height, min - left_len);
+        } else if right_len < min {
+            bulk_steal_left(left.node, 0, height, min - right_len);
+        }
+        left
+    } else if left.height > right.height {
+        let mut node = left.node;
+        for _ in right.height + 1..left.height {
+            node = edge(node, len(node));
+        }
+        let idx = len(node);
+        let added = 1 + subtree_len(right.node, right.height);
+        insert_into(node, right.height + 1, idx, key, val, Some(right.node), added, order, &mut left);
+        fix_border(&mut left, order, true);
+        left
+    } else {
+        let mut node = right.node;
+        for _ in left.height + 1..right.height {
+            node = edge(node, 0);
+        }
+        let first = edge(node, 0);
+        *edge_ptr(node, 0) = left.node;
+        correct_parent_link(node, 0);
+        let added = 1 + subtree_len(left.node, left.height);
+        insert_into(node, left.height + 1, 0, key, val, Some(first), added, order, &mut right);
+        fix_border(&mut right, order, false);
+        right
+    }
+}
+
+// Checks node occupancy, parent links, cached subtree sizes and key order
+// under `less`, returning the number of entries below `node`. Leaves are
+// whatever sits `height` levels down, so with every parent link pointing
+// back up the same path, all of them share the root's depth.
+pub(crate) unsafe fn validate_subtree<K, V, const B: usize, S, F>(
+    node: NodePtr<K, V, B, S>,
+    height: usize,
+    order: usize,
+    is_root: bool,
+    less: &F,
+) -> Result<usize, CroInvariantError>
+where
+    F: Fn(&K, &K) -> bool,
+{
+    let len = len(node);
+    let min = match (is_root, height) {
+        (false, _) => min_len(order),
+        (true, 0) => 0,
+        (true, _) => 1,
+    };
+    if len < min || len > capacity(order) {
+        return Err(CroInvariantError::NodeOccupancy {
+            height,
+            len,
+            min,
+            max: capacity(order),
+        });
+    }
+    for idx in 1..len {
+        if !less(&*key_ptr(node, idx - 1), &*key_ptr(node, idx)) {
+            return Err(CroInvariantError::KeysOutOfOrder { height });
+        }
+    }
+    let mut count = len;
+    if height > 0 {
+        for idx in 0..=len {
+            let child = edge(node, idx);
+            if parent(child) != Some((node, idx)) {
+                return Err(CroInvariantError::StaleParentLink { height });
+            }
+            count += validate_subtree(child, height - 1, order, false, less)?;
+            // The child must fit between the separators on either side.
+            if idx > 0 {
+                if let Some(first) = first_leaf_edge(child, height - 1).next_kv() {
+                    if !less(&*key_ptr(node, idx - 1), first.key()) {
+                        return Err(CroInvariantError::KeysOutOfOrder { height });
+                    }
+                }
+            }
+            if idx < len {
+                if let Some(last) = last_leaf_edge(child, height - 1).prev_kv() {
+                    if !less(last.key(), &*key_ptr(node, idx)) {
+                        return Err(CroInvariantError::KeysOutOfOrder { height });
+                    }
+                }
+            }
+        }
+        let stored = subtree_len(node, height);
+        if stored != count {
+            return Err(CroInvariantError::LenMismatch { stored, counted: count });
+        }
+    }
+    Ok(count)
+}
@@ -1,92 +1,156 @@
 use std::borrow::Borrow;
 use std::collections::hash_map::RandomState;
-use std::hash::{BuildHasher, Hash, Hasher};
-use std::mem::ManuallyDrop;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+
+use super::hash_table::{bucket_capacity, buckets_for_capacity, RawTable, GROUP_WIDTH};
+use crate::hash::CroSipState;
+use crate::invariants::{check_invariants, CroInvariantError};
+use crate::sequences::CroVec;
+
+// With incremental rehashing on, growing the map swaps in the new table and
+// keeps the old one in `old`. Each insert or removal then moves the entries
+// of a few old buckets across, and lookups check both tables until the old
+// one is empty, so no single call pays for the whole rehash.
+pub struct CroMap<K, V, S = RandomState> {
+    table: RawTable<K, V>,
+    old: Option<RawTable<K, V>>,
+    // Old buckets below this index have already been moved.
+    rehash_index: usize,
+    incremental: bool,
+    build_hasher: S,
+    // Replaces `build_hasher` once inserts have probed too far, which with a
+    // fast unkeyed hasher usually means the keys were chosen to collide.
+    secure: Option<CroSipState>,
+    probe_limit: Option<usize>,
+    max_probe: usize,
+    reseeds: usize,
+}
 
-use crate::sequences::vec::CroVec;
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CroMapStats {
+    pub size: usize,
+    pub cap: usize,
+    // Longest insert probe, in groups of buckets, since the last full rehash.
+    pub max_probe: usize,
+    pub probe_limit: Option<usize>,
+    pub reseeds: usize,
+}
 
-#[derive(Debug)]
-struct Entry<K, V> {
-    key: Option<K>,
-    value: Option<V>,
-    tombstone: bool,
+pub enum CroMapEntry<'a, K, V, S = RandomState> {
+    Occupied(CroMapOccupiedEntry<'a, K, V, S>),
+    Vacant(CroMapVacantEntry<'a, K, V, S>),
 }
 
-#[derive(Debug)]
-pub struct CroMap<K, V, S = RandomState> {
-    entries: CroVec<Entry<K, V>>,
-    size: usize,
-    build_hasher: S,
+pub struct CroMapOccupiedEntry<'a, K, V, S = RandomState> {
+    raw: CroMapRawOccupiedEntry<'a, K, V, S>,
 }
 
-impl<K, V> Entry<K, V> {
-    fn new() -> Self {
-        Entry {
-            key: None,
-            value: None,
-            tombstone: false,
-        }
-    }
+pub struct CroMapVacantEntry<'a, K, V, S = RandomState> {
+    raw: CroMapRawVacantEntry<'a, K, V, S>,
+    key: K,
+}
 
-    fn insert(&mut self, key: K, value: V) {
-        self.key = Some(key);
-        self.value = Some(value);
-        self.tombstone = false;
-    }
+pub struct CroMapRawEntryBuilder<'a, K, V, S> {
+    map: &'a CroMap<K, V, S>,
+}
+
+pub struct CroMapRawEntryBuilderMut<'a, K, V, S> {
+    map: &'a mut CroMap<K, V, S>,
+}
+
+pub enum CroMapRawEntryMut<'a, K, V, S> {
+    Occupied(CroMapRawOccupiedEntry<'a, K, V, S>),
+    Vacant(CroMapRawVacantEntry<'a, K, V, S>),
+}
+
+pub struct CroMapRawOccupiedEntry<'a, K, V, S> {
+    map: &'a mut CroMap<K, V, S>,
+    // Position as numbered by `find_slot_with_hash`.
+    pos: usize,
+}
+
+pub struct CroMapRawVacantEntry<'a, K, V, S> {
+    map: &'a mut CroMap<K, V, S>,
+    hash: u64,
 }
 
 pub struct CroMapIter<'a, K, V, S> {
     map: &'a CroMap<K, V, S>,
     index: usize,
+    remaining: usize,
 }
 
 pub struct CroMapIntoIter<K, V, S> {
-    entries: CroVec<Entry<K, V>>,
-    index: usize,
+    tables: OwnedTables<K, V>,
     _hasher: S,
 }
 
 pub struct CroMapDrain<K, V> {
-    entries: CroVec<Entry<K, V>>,
+    tables: OwnedTables<K, V>,
+}
+
+// Tables handed over to a consuming iterator. Positions past the end of
+// `table` continue into `old`.
+struct OwnedTables<K, V> {
+    table: RawTable<K, V>,
+    old: Option<RawTable<K, V>>,
     index: usize,
 }
 
+impl<K, V> OwnedTables<K, V> {
+    fn len(&self) -> usize {
+        self.table.len() + self.old.as_ref().map_or(0, |old| old.len())
+    }
+
+    fn take_next(&mut self) -> Option<(K, V)> {
+        let buckets = self.table.buckets();
+        if self.index < buckets {
+            if let Some(full) = self.table.next_full(self.index) {
+                self.index = full + 1;
+                return Some(unsafe { self.table.take(full) });
+            }
+            self.index = buckets;
+        }
+        let old = self.old.as_mut()?;
+        let full = old.next_full(self.index - buckets)?;
+        self.index = buckets + full + 1;
+        Some(unsafe { old.take(full) })
+    }
+}
+
 impl<'a, K, V, S> Iterator for CroMapIter<'a, K, V, S> {
     type Item = (&'a K, &'a V);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let len = self.map.entries.size();
-        while self.index < len {
-            let index = self.index;
-            self.index += 1;
-            let entry = unsafe { &*self.map.entries.data.add(index) };
-            if !entry.tombstone {
-                if let (Some(ref key), Some(ref value)) = (&entry.key, &entry.value) {
-                    return Some((key, value));
-                }
-            }
-        }
-        None
+        let (index, (key, value)) = self.map.next_entry(self.index)?;
+        self.index = index + 1;
+        self.remaining -= 1;
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
 }
 
+impl<K, V, S> ExactSizeIterator for CroMapIter<'_, K, V, S> {}
+
+impl<K, V, S> ExactSizeIterator for CroMapIntoIter<K, V, S> {}
+
+impl<K, V> ExactSizeIterator for CroMapDrain<K, V> {}
+
 impl<K, V, S> Iterator for CroMapIntoIter<K, V, S> {
     type Item = (K, V);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let len = self.entries.size();
-        while self.index < len {
-            let index = self.index;
-            self.index += 1;
-            let entry = unsafe { &mut *self.entries.data.add(index) };
-            if entry.tombstone {
-                continue;
-            }
-            if let (Some(key), Some(value)) = (entry.key.take(), entry.value.take()) {
-                return Some((key, value));
-            }
-        }
-        None
+        self.tables.take_next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.tables.len(), Some(self.tables.len()))
     }
 }
 
@@ -94,34 +158,42 @@ impl<K, V> Iterator for CroMapDrain<K, V> {
     type Item = (K, V);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let len = self.entries.size();
-        while self.index < len {
-            let index = self.index;
-            self.index += 1;
-            let entry = unsafe { &mut *self.entries.data.add(index) };
-            if entry.tombstone {
-                continue;
-            }
-            if let (Some(key), Some(value)) = (entry.key.take(), entry.value.take()) {
-                return Some((key, value));
-            }
-        }
-        None
+        self.tables.take_next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.tables.len(), Some(self.tables.len()))
     }
 }
 
 const DEFAULT_CAP: usize = 16;
 
-fn init_entries<K, V>(cap: usize) -> CroVec<Entry<K, V>> {
-    let mut entries = CroVec::with_cap(cap);
-    for _ in 0..cap {
-        entries.push(Entry::new());
-    }
-    entries
+// Old buckets moved per insert or removal while an incremental rehash runs.
+// One group per call finishes the move well before the new table fills.
+const REHASH_STEP: usize = GROUP_WIDTH;
+
+// Probe length, in groups, past which inserts switch to a secure hasher.
+// Random hashes at the maximum load factor stay far below it.
+const DEFAULT_PROBE_LIMIT: usize = 32;
+
+// Set on scan cursors handed out after the map has reseeded. Real cursors
+// stay below the bucket count, so the top bit is free.
+const SCAN_RESEEDED: usize = 1 << (usize::BITS - 1);
+
+// `cap` counts buckets, rounded up to a power of two of at least one group.
+fn buckets_for_cap(cap: usize) -> usize {
+    cap.max(GROUP_WIDTH).next_power_of_two()
 }
 
-fn max_size_for_cap(cap: usize) -> usize {
-    cap - (cap / 4)
+fn hash_key<Q, S>(secure: Option<&CroSipState>, build_hasher: &S, key: &Q) -> u64
+where
+    Q: Hash + ?Sized,
+    S: BuildHasher,
+{
+    match secure {
+        Some(secure) => secure.hash_one(key),
+        None => build_hasher.hash_one(key),
+    }
 }
 
 impl<K, V> CroMap<K, V>
@@ -133,6 +205,104 @@ where
     }
 }
 
+impl<K, V, S> CroMap<K, V, S> {
+    pub fn iter(&self) -> CroMapIter<'_, K, V, S> {
+        CroMapIter {
+            map: self,
+            index: 0,
+            remaining: self.size(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.table.len() + self.old.as_ref().map_or(0, |old| old.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    // Old buckets moved so far and the old table's bucket count, while an
+    // incremental rehash is under way.
+    pub fn rehash_progress(&self) -> Option<(usize, usize)> {
+        self.old.as_ref().map(|old| (self.rehash_index, old.buckets()))
+    }
+
+    // Next entry at or after `index`, numbering the current table's buckets
+    // first and the old table's after them.
+    fn next_entry(&self, index: usize) -> Option<(usize, &(K, V))> {
+        let buckets = self.table.buckets();
+        if index < buckets {
+            if let Some(full) = self.table.next_full(index) {
+                return Some((full, unsafe { self.table.slot(full) }));
+            }
+        }
+        let old = self.old.as_ref()?;
+        let full = old.next_full(index.saturating_sub(buckets))?;
+        Some((buckets + full, unsafe { old.slot(full) }))
+    }
+
+    // Finds the entry for `hash` accepted by `eq`, numbering positions the
+    // same way as `next_entry`.
+    fn find_slot_with_hash<F>(&self, hash: u64, mut eq: F) -> Option<usize>
+    where
+        F: FnMut(&K) -> bool,
+    {
+        if let Some(index) = self.table.find(hash, &mut eq) {
+            return Some(index);
+        }
+        let old = self.old.as_ref()?;
+        Some(self.table.buckets() + old.find(hash, eq)?)
+    }
+
+    // `pos` must come from `find_slot_with_hash` with no changes since.
+    unsafe fn slot_at(&self, pos: usize) -> &(K, V) {
+        match pos.checked_sub(self.table.buckets()) {
+            None => self.table.slot(pos),
+            Some(index) => self.old.as_ref().unwrap().slot(index),
+        }
+    }
+
+    unsafe fn slot_at_mut(&mut self, pos: usize) -> &mut (K, V) {
+        match pos.checked_sub(self.table.buckets()) {
+            None => self.table.slot_mut(pos),
+            Some(index) => self.old.as_mut().unwrap().slot_mut(index),
+        }
+    }
+
+    unsafe fn take_at(&mut self, pos: usize) -> (K, V) {
+        match pos.checked_sub(self.table.buckets()) {
+            None => self.table.take(pos),
+            Some(index) => self.old.as_mut().unwrap().take(index),
+        }
+    }
+
+    pub fn get_with_hash<F>(&self, hash: u64, eq: F) -> Option<&V>
+    where
+        F: FnMut(&K) -> bool,
+    {
+        let pos = self.find_slot_with_hash(hash, eq)?;
+        Some(unsafe { &self.slot_at(pos).1 })
+    }
+
+    pub fn raw_entry(&self) -> CroMapRawEntryBuilder<'_, K, V, S> {
+        CroMapRawEntryBuilder { map: self }
+    }
+
+    // The part of `validate` that needs no hashing, for mutations made
+    // without the bounds to hash keys.
+    fn validate_tables(&self) -> Result<(), CroInvariantError> {
+        self.table.validate_ctrl()?;
+        if let Some(old) = &self.old {
+            old.validate_ctrl()?;
+            if let Some(bucket) = old.next_full(0).filter(|&bucket| bucket < self.rehash_index) {
+                return Err(CroInvariantError::UnmovedEntry { bucket });
+            }
+        }
+        Ok(())
+    }
+}
+
 impl<K, V, S> CroMap<K, V, S>
 where
     K: Hash + Eq,
@@ -150,45 +320,163 @@ where
     }
 
     pub fn with_cap_and_hasher(cap: usize, build_hasher: S) -> Self {
-        let cap = if cap == 0 { 1 } else { cap };
         CroMap {
-            entries: init_entries(cap),
-            size: 0,
+            table: RawTable::with_buckets(buckets_for_cap(cap)),
+            old: None,
+            rehash_index: 0,
+            incremental: false,
             build_hasher,
+            secure: None,
+            probe_limit: Some(DEFAULT_PROBE_LIMIT),
+            max_probe: 0,
+            reseeds: 0,
         }
     }
 
+    // `None` turns detection off.
+    pub fn set_probe_limit(&mut self, limit: Option<usize>) {
+        self.probe_limit = limit;
+    }
+
+    pub fn stats(&self) -> CroMapStats {
+        CroMapStats {
+            size: self.size(),
+            cap: self.cap(),
+            max_probe: self.max_probe,
+            probe_limit: self.probe_limit,
+            reseeds: self.reseeds,
+        }
+    }
+
+    // Turning it off finishes any rehash that is under way.
+    pub fn set_incremental_rehash(&mut self, incremental: bool) {
+        self.incremental = incremental;
+        if !incremental {
+            self.finish_rehash();
+        }
+        check_invariants!(self.validate());
+    }
+
     pub fn hasher(&self) -> &S {
         &self.build_hasher
     }
 
     pub fn cap(&self) -> usize {
-        self.entries.cap()
+        self.table.buckets()
     }
 
-    pub fn size(&self) -> usize {
-        self.size
+    // Checks both tables' bookkeeping, then every entry's hash fragment and
+    // that a lookup for it finds it before hitting an empty bucket.
+    pub fn validate(&self) -> Result<(), CroInvariantError> {
+        self.validate_tables()?;
+        for table in [Some(&self.table), self.old.as_ref()].into_iter().flatten() {
+            table.validate_entries(|key, _| self.hash(key))?;
+        }
+        Ok(())
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.size == 0
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.hash(&key);
+        self.insert_with_hash(hash, key, value)
     }
 
-    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
-        self.ensure_capacity(1);
+    // Hashes computed before a reseed no longer match; `stats().reseeds`
+    // tells callers caching them when to recompute.
+    pub fn hash_of<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        self.hash(key)
+    }
 
-        let index = self.find_slot(&key);
-        let entry = unsafe { &mut *self.entries.data.add(index) };
+    // `hash` must be `hash_of(&key)`.
+    pub fn insert_with_hash(&mut self, hash: u64, key: K, value: V) -> Option<V> {
+        debug_assert_eq!(hash, self.hash(&key));
+        self.rehash_step();
+        if let Some(pos) = self.find_slot_with_hash(hash, |existing| *existing == key) {
+            let slot = unsafe { self.slot_at_mut(pos) };
+            let old = mem::replace(&mut slot.1, value);
+            check_invariants!(self.validate());
+            return Some(old);
+        }
+        self.insert_new(hash, key, value);
+        check_invariants!(self.validate());
+        None
+    }
 
-        let old_value = if entry.key.is_some() && !entry.tombstone {
-            entry.value.take()
-        } else {
-            self.size += 1;
-            None
-        };
+    pub fn remove_with_hash<F>(&mut self, hash: u64, eq: F) -> Option<(K, V)>
+    where
+        F: FnMut(&K) -> bool,
+    {
+        self.rehash_step();
+        let removed = self.find_slot_with_hash(hash, eq).map(|pos| unsafe { self.take_at(pos) });
+        check_invariants!(self.validate());
+        removed
+    }
 
-        entry.insert(key, value);
-        old_value
+    pub fn raw_entry_mut(&mut self) -> CroMapRawEntryBuilderMut<'_, K, V, S> {
+        self.rehash_step();
+        check_invariants!(self.validate());
+        CroMapRawEntryBuilderMut { map: self }
+    }
+
+    // Inserts a key known to be absent and returns its bucket in `table`.
+    fn insert_new(&mut self, hash: u64, key: K, value: V) -> usize {
+        let (mut index, mut probe) = self.table.find_insert_slot(hash);
+        if self.table.growth_left() == 0 && self.table.is_empty_slot(index) {
+            self.ensure_capacity(1);
+            (index, probe) = self.table.find_insert_slot(hash);
+        }
+        let mut hash = hash;
+        if self.probe_limit.is_some_and(|limit| probe > limit) && self.secure.is_none() {
+            self.reseed();
+            hash = self.hash(&key);
+            (index, probe) = self.table.find_insert_slot(hash);
+        }
+        unsafe { self.table.insert_at(index, hash, key, value) };
+        self.max_probe = self.max_probe.max(probe);
+        index
+    }
+
+    // Pages through the map without holding a borrow between calls, like
+    // Redis SCAN: start at cursor 0 and pass each returned cursor back in
+    // until it comes back as 0. Each call visits home buckets until it has
+    // about `count` entries. Cursors advance in reverse-binary order, so
+    // after the table doubles or halves the buckets already visited map onto
+    // cursors that have already been passed, and an entry present for the
+    // whole scan is returned at least once, possibly more. A reseed moves
+    // every entry to a new home bucket instead, so a cursor from before it
+    // starts the scan over under the new hash. A map reseeds at most once,
+    // so this happens at most once per scan.
+    pub fn scan(&self, cursor: usize, count: usize) -> (usize, CroVec<(&K, &V)>) {
+        let epoch = if self.secure.is_some() { SCAN_RESEEDED } else { 0 };
+        let mut cursor = if cursor & SCAN_RESEEDED == epoch { cursor & !SCAN_RESEEDED } else { 0 };
+        let mut found = CroVec::new();
+        let small = self.old.as_ref().map_or(self.table.buckets(), |old| {
+            old.buckets().min(self.table.buckets())
+        });
+        let mask = small - 1;
+        loop {
+            // With a rehash under way, the larger table's home buckets that
+            // reduce to this one are visited along with it.
+            for table in [Some(&self.table), self.old.as_ref()].into_iter().flatten() {
+                let table_mask = table.buckets() - 1;
+                let mut home = cursor & mask;
+                while home < table.buckets() {
+                    table.for_each_probed(home, |index| {
+                        let (key, value) = unsafe { table.slot(index) };
+                        if self.hash(key) as usize & table_mask == home {
+                            found.push((key, value));
+                        }
+                    });
+                    home += small;
+                }
+            }
+            cursor = (cursor | !mask).reverse_bits().wrapping_add(1).reverse_bits();
+            if cursor == 0 {
+                return (0, found);
+            }
+            if found.size() >= count {
+                return (cursor | epoch, found);
+            }
+        }
     }
 
     pub fn get<Q>(&self, key: &Q) -> Option<&V>
@@ -196,53 +484,49 @@ where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        let hash = self.hash(key);
-        let index = self.find_slot_with_hash::<Q, _>(hash, |existing| existing.borrow() == key);
-        let entry = unsafe { &*self.entries.data.add(index) };
+        self.get_key_value(key).map(|(_, value)| value)
+    }
 
-        if entry.key.is_some() && !entry.tombstone {
-            entry.value.as_ref()
-        } else {
-            None
-        }
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let pos = self.find_slot_with_hash(self.hash(key), |existing| existing.borrow() == key)?;
+        Some(unsafe { &mut self.slot_at_mut(pos).1 })
     }
 
-    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
     where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        let hash = self.hash(key);
-        let index = self.find_slot_with_hash::<Q, _>(hash, |existing| existing.borrow() == key);
-        let entry = unsafe { &*self.entries.data.add(index) };
+        self.get(key).is_some()
+    }
 
-        if entry.key.is_some() && !entry.tombstone {
-            match (&entry.key, &entry.value) {
-                (Some(k), Some(v)) => Some((k, v)),
-                _ => None,
-            }
-        } else {
-            None
+    pub fn entry(&mut self, key: K) -> CroMapEntry<'_, K, V, S> {
+        let hash = self.hash(&key);
+        match self.raw_entry_mut().from_key_hashed_nocheck(hash, &key) {
+            CroMapRawEntryMut::Occupied(raw) => CroMapEntry::Occupied(CroMapOccupiedEntry { raw }),
+            CroMapRawEntryMut::Vacant(raw) => CroMapEntry::Vacant(CroMapVacantEntry { raw, key }),
         }
     }
 
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.raw_entry().from_key(key)
+    }
+
     pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
     where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
         let hash = self.hash(key);
-        let index = self.find_slot_with_hash::<Q, _>(hash, |existing| existing.borrow() == key);
-        let entry = unsafe { &mut *self.entries.data.add(index) };
-
-        if entry.key.is_some() && !entry.tombstone {
-            entry.tombstone = true;
-            self.size -= 1;
-            if let (Some(k), Some(v)) = (entry.key.take(), entry.value.take()) {
-                return Some((k, v));
-            }
-        }
-        None
+        self.remove_with_hash(hash, |existing| existing.borrow() == key)
     }
 
     pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
@@ -253,31 +537,60 @@ where
         self.remove_entry(key).map(|(_, value)| value)
     }
 
-    pub fn iter(&self) -> CroMapIter<'_, K, V, S> {
-        CroMapIter { map: self, index: 0 }
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        for table in [Some(&mut self.table), self.old.as_mut()].into_iter().flatten() {
+            let mut index = 0;
+            while let Some(full) = table.next_full(index) {
+                let (key, value) = unsafe { table.slot_mut(full) };
+                if !f(key, value) {
+                    drop(unsafe { table.take(full) });
+                }
+                index = full + 1;
+            }
+        }
+        check_invariants!(self.validate());
     }
 
     pub fn drain(&mut self) -> CroMapDrain<K, V> {
-        let cap = self.entries.cap();
-        let entries = std::mem::replace(&mut self.entries, init_entries(cap));
-        self.size = 0;
-        CroMapDrain { entries, index: 0 }
+        let buckets = self.table.buckets();
+        let drain = CroMapDrain {
+            tables: OwnedTables {
+                table: mem::replace(&mut self.table, RawTable::with_buckets(buckets)),
+                old: self.old.take(),
+                index: 0,
+            },
+        };
+        check_invariants!(self.validate());
+        drain
+    }
+
+    // Splits the map into its entries and its hasher, for conversions that
+    // carry the hasher over.
+    pub(crate) fn into_parts(self) -> (CroMapDrain<K, V>, S) {
+        let drain = CroMapDrain {
+            tables: OwnedTables {
+                table: self.table,
+                old: self.old,
+                index: 0,
+            },
+        };
+        (drain, self.build_hasher)
     }
 
     pub fn wipe(&mut self) {
-        for i in 0..self.entries.size() {
-            unsafe {
-                let entry = &mut *self.entries.data.add(i);
-                entry.key.take();
-                entry.value.take();
-                entry.tombstone = false;
-            }
-        }
-        self.size = 0;
+        self.table.clear();
+        self.old = None;
+        check_invariants!(self.validate());
     }
 
     pub fn reserve(&mut self, additional: usize) {
-        self.ensure_capacity(additional);
+        if additional > self.table.growth_left() {
+            self.ensure_capacity(additional);
+        }
+        check_invariants!(self.validate());
     }
 
     pub fn shrink_to_fit(&mut self) {
@@ -285,85 +598,111 @@ where
     }
 
     pub fn shrink_to(&mut self, min_cap: usize) {
-        let mut cap = if min_cap == 0 { 1 } else { min_cap };
-        while self.size > max_size_for_cap(cap) {
-            cap *= 2;
-        }
-        if cap < self.entries.cap() {
-            self.rehash(cap);
+        let buckets = buckets_for_cap(min_cap).max(buckets_for_capacity(self.size()));
+        if buckets < self.table.buckets() {
+            self.rehash(buckets);
         }
+        check_invariants!(self.validate());
     }
 
-    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
-        let mut hasher = self.build_hasher.build_hasher();
-        key.hash(&mut hasher);
-        hasher.finish() as usize
+    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        hash_key(self.secure.as_ref(), &self.build_hasher, key)
     }
 
-    fn find_slot(&self, key: &K) -> usize {
-        let hash = self.hash(key);
-        self.find_slot_with_hash::<K, _>(hash, |existing| existing == key)
+    // Switches to a freshly keyed SipHash and rebuilds the table with it.
+    fn reseed(&mut self) {
+        self.secure = Some(CroSipState::new());
+        self.reseeds += 1;
+        self.rehash(self.table.buckets());
     }
 
-    fn find_slot_with_hash<Q: ?Sized, F>(&self, hash: usize, mut matches: F) -> usize
-    where
-        F: FnMut(&K) -> bool,
-    {
-        let cap = self.entries.cap();
-        let mut index = hash % cap;
-        let mut first_tombstone = None;
-
-        loop {
-            let entry = unsafe { &*self.entries.data.add(index) };
-
-            if entry.key.is_none() && !entry.tombstone {
-                return first_tombstone.unwrap_or(index);
-            }
-
-            if entry.tombstone && first_tombstone.is_none() {
-                first_tombstone = Some(index);
-            }
+    // Makes room for `additional` more inserts. When tombstones rather than
+    // live entries are what filled the table, it is rebuilt at the same size.
+    fn ensure_capacity(&mut self, additional: usize) {
+        let required = self.size() + additional;
+        let buckets = self.table.buckets();
+        let target = if self.old.is_none() && required <= bucket_capacity(buckets) / 2 {
+            buckets
+        } else {
+            buckets_for_capacity(required.max(bucket_capacity(buckets) + 1))
+        };
+        if self.incremental && self.old.is_none() {
+            self.old = Some(mem::replace(&mut self.table, RawTable::with_buckets(target)));
+            self.rehash_index = 0;
+        } else {
+            self.rehash(target);
+        }
+    }
 
-            if let Some(ref existing_key) = entry.key {
-                if !entry.tombstone && matches(existing_key) {
-                    return index;
-                }
+    // Moves everything, including any entries still in the old table, into a
+    // fresh table at once.
+    fn rehash(&mut self, buckets: usize) {
+        self.max_probe = 0;
+        let table = mem::replace(&mut self.table, RawTable::with_buckets(buckets));
+        for mut from in [Some(table), self.old.take()].into_iter().flatten() {
+            let mut index = 0;
+            while let Some(full) = from.next_full(index) {
+                let (key, value) = unsafe { from.take(full) };
+                let hash = self.hash(&key);
+                self.table.insert_unique(hash, key, value);
+                index = full + 1;
             }
-
-            index = (index + 1) % cap;
         }
     }
 
-    fn ensure_capacity(&mut self, additional: usize) {
-        let required = self.size + additional;
-        let mut cap = self.entries.cap();
-        if required <= max_size_for_cap(cap) {
+    fn rehash_step(&mut self) {
+        let old = match self.old.as_mut() {
+            Some(old) => old,
+            None => return,
+        };
+        // Tombstones can use up the new table before the move is done; fall
+        // back to a full rehash rather than overfilling it.
+        if self.table.growth_left() < REHASH_STEP {
+            let buckets = buckets_for_capacity(self.size() + REHASH_STEP).max(self.table.buckets());
+            self.rehash(buckets);
             return;
         }
-        while required > max_size_for_cap(cap) {
-            cap *= 2;
+        let end = (self.rehash_index + REHASH_STEP).min(old.buckets());
+        let mut index = self.rehash_index;
+        while let Some(full) = old.next_full(index).filter(|&full| full < end) {
+            let (key, value) = unsafe { old.take(full) };
+            let hash = hash_key(self.secure.as_ref(), &self.build_hasher, &key);
+            self.table.insert_unique(hash, key, value);
+            index = full + 1;
+        }
+        self.rehash_index = end;
+        if end == old.buckets() || old.len() == 0 {
+            self.old = None;
         }
-        self.rehash(cap);
     }
 
-    fn rehash(&mut self, new_cap: usize) {
-        let old_entries = std::mem::replace(&mut self.entries, init_entries(new_cap));
-        self.size = 0;
-
-        for i in 0..old_entries.size() {
-            unsafe {
-                let entry = &mut *old_entries.data.add(i);
-                if entry.tombstone {
-                    continue;
-                }
-                if let (Some(key), Some(value)) = (entry.key.take(), entry.value.take()) {
-                    self.insert(key, value);
-                }
-            }
+    fn finish_rehash(&mut self) {
+        while self.old.is_some() {
+            self.rehash_step();
         }
     }
 }
 
+impl<K, V, S> Default for CroMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: Default + BuildHasher,
+{
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K, V, S> fmt::Debug for CroMap<K, V, S>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
 impl<K, V, S> IntoIterator for CroMap<K, V, S>
 where
     K: Hash + Eq,
@@ -373,19 +712,299 @@ where
     type IntoIter = CroMapIntoIter<K, V, S>;
 
     fn into_iter(self) -> Self::IntoIter {
-        let mut map = ManuallyDrop::new(self);
-        let entries = unsafe { std::ptr::read(&map.entries) };
-        let build_hasher = unsafe { std::ptr::read(&map.build_hasher) };
         CroMapIntoIter {
-            entries,
-            index: 0,
-            _hasher: build_hasher,
+            tables: OwnedTables {
+                table: self.table,
+                old: self.old,
+                index: 0,
+            },
+            _hasher: self.build_hasher,
+        }
+    }
+}
+
+// Keys coming out of a `HashMap` are already distinct, so they skip the
+// lookup. The hasher is cloned, since `HashMap` only lends it out.
+impl<K, V, S> From<HashMap<K, V, S>> for CroMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Clone,
+{
+    fn from(map: HashMap<K, V, S>) -> Self {
+        let mut out = CroMap::with_cap_and_hasher(0, map.hasher().clone());
+        out.reserve(map.len());
+        for (key, value) in map {
+            let hash = out.hash(&key);
+            out.insert_new(hash, key, value);
         }
+        check_invariants!(out.validate());
+        out
     }
 }
 
-impl<K, V, S> Drop for CroMap<K, V, S> {
-    fn drop(&mut self) {
-        self.entries.wipe();
+impl<K, V, S> From<CroMap<K, V, S>> for HashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn from(map: CroMap<K, V, S>) -> Self {
+        let (entries, build_hasher) = map.into_parts();
+        let mut out = HashMap::with_capacity_and_hasher(entries.len(), build_hasher);
+        out.extend(entries);
+        out
+    }
+}
+
+impl<'a, K, V, S> CroMapEntry<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn key(&self) -> &K {
+        match self {
+            CroMapEntry::Occupied(entry) => entry.key(),
+            CroMapEntry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            CroMapEntry::Occupied(entry) => entry.into_mut(),
+            CroMapEntry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            CroMapEntry::Occupied(entry) => entry.into_mut(),
+            CroMapEntry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        if let CroMapEntry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K, V, S> CroMapOccupiedEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        self.raw.key()
+    }
+
+    pub fn get(&self) -> &V {
+        self.raw.get()
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.raw.get_mut()
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.raw.into_mut()
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        self.raw.insert(value)
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        self.raw.remove_entry()
+    }
+
+    pub fn remove(self) -> V {
+        self.raw.remove()
+    }
+}
+
+impl<'a, K, V, S> CroMapVacantEntry<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.raw.insert(self.key, value).1
+    }
+}
+
+impl<'a, K, V, S> CroMapRawEntryBuilder<'a, K, V, S>
+where
+    S: BuildHasher,
+{
+    pub fn from_key<Q>(self, key: &Q) -> Option<(&'a K, &'a V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = hash_key(self.map.secure.as_ref(), &self.map.build_hasher, key);
+        self.from_key_hashed_nocheck(hash, key)
+    }
+
+    pub fn from_key_hashed_nocheck<Q>(self, hash: u64, key: &Q) -> Option<(&'a K, &'a V)>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        self.from_hash(hash, |existing| existing.borrow() == key)
+    }
+
+    pub fn from_hash<F>(self, hash: u64, eq: F) -> Option<(&'a K, &'a V)>
+    where
+        F: FnMut(&K) -> bool,
+    {
+        let pos = self.map.find_slot_with_hash(hash, eq)?;
+        let (key, value) = unsafe { self.map.slot_at(pos) };
+        Some((key, value))
+    }
+}
+
+impl<'a, K, V, S> CroMapRawEntryBuilderMut<'a, K, V, S>
+where
+    S: BuildHasher,
+{
+    pub fn from_key<Q>(self, key: &Q) -> CroMapRawEntryMut<'a, K, V, S>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = hash_key(self.map.secure.as_ref(), &self.map.build_hasher, key);
+        self.from_key_hashed_nocheck(hash, key)
+    }
+
+    pub fn from_key_hashed_nocheck<Q>(self, hash: u64, key: &Q) -> CroMapRawEntryMut<'a, K, V, S>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        self.from_hash(hash, |existing| existing.borrow() == key)
+    }
+
+    pub fn from_hash<F>(self, hash: u64, eq: F) -> CroMapRawEntryMut<'a, K, V, S>
+    where
+        F: FnMut(&K) -> bool,
+    {
+        match self.map.find_slot_with_hash(hash, eq) {
+            Some(pos) => CroMapRawEntryMut::Occupied(CroMapRawOccupiedEntry { map: self.map, pos }),
+            None => CroMapRawEntryMut::Vacant(CroMapRawVacantEntry {
+                map: self.map,
+                hash,
+            }),
+        }
+    }
+}
+
+impl<'a, K, V, S> CroMapRawEntryMut<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn or_insert(self, key: K, value: V) -> (&'a mut K, &'a mut V) {
+        self.or_insert_with(|| (key, value))
+    }
+
+    pub fn or_insert_with<F>(self, default: F) -> (&'a mut K, &'a mut V)
+    where
+        F: FnOnce() -> (K, V),
+    {
+        match self {
+            CroMapRawEntryMut::Occupied(entry) => entry.into_key_value(),
+            CroMapRawEntryMut::Vacant(entry) => {
+                let (key, value) = default();
+                entry.insert(key, value)
+            }
+        }
+    }
+
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut K, &mut V),
+    {
+        if let CroMapRawEntryMut::Occupied(entry) = &mut self {
+            let (key, value) = entry.get_key_value_mut();
+            f(key, value);
+        }
+        self
+    }
+}
+
+impl<'a, K, V, S> CroMapRawOccupiedEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        unsafe { &self.map.slot_at(self.pos).0 }
+    }
+
+    pub fn get(&self) -> &V {
+        unsafe { &self.map.slot_at(self.pos).1 }
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe { &mut self.map.slot_at_mut(self.pos).1 }
+    }
+
+    // Changing the key must not change its hash or its equality with other
+    // keys in the map.
+    pub fn get_key_value_mut(&mut self) -> (&mut K, &mut V) {
+        let (key, value) = unsafe { self.map.slot_at_mut(self.pos) };
+        (key, value)
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        unsafe { &mut self.map.slot_at_mut(self.pos).1 }
+    }
+
+    pub fn into_key_value(self) -> (&'a mut K, &'a mut V) {
+        let (key, value) = unsafe { self.map.slot_at_mut(self.pos) };
+        (key, value)
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        let removed = unsafe { self.map.take_at(self.pos) };
+        check_invariants!(self.map.validate_tables());
+        removed
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+}
+
+impl<'a, K, V, S> CroMapRawVacantEntry<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    // `key` must hash to the hash this entry was looked up with.
+    pub fn insert(self, key: K, value: V) -> (&'a mut K, &'a mut V) {
+        debug_assert_eq!(self.hash, self.map.hash(&key));
+        let index = self.map.insert_new(self.hash, key, value);
+        check_invariants!(self.map.validate());
+        let (key, value) = unsafe { self.map.table.slot_mut(index) };
+        (key, value)
     }
 }
@@ -0,0 +1,366 @@
+use std::mem::{self, MaybeUninit};
+use std::ptr;
+
+use crate::invariants::CroInvariantError;
+
+// Open-addressing storage for `CroMap`, laid out like a SwissTable. Every
+// bucket has a control byte: EMPTY, DELETED, or the top seven bits of the
+// key's hash when the bucket is full. Lookups load the control bytes a group
+// (one u64) at a time and compare all of them against the hash fragment with
+// plain integer arithmetic, so keys are only compared for likely matches.
+// The bucket count is a power of two of at least one group, and the first
+// group of control bytes is mirrored past the end so that a group can be
+// loaded starting at any bucket.
+
+pub(crate) const GROUP_WIDTH: usize = mem::size_of::<u64>();
+
+const EMPTY: u8 = 0b1111_1111;
+const DELETED: u8 = 0b1000_0000;
+
+const LO_BITS: u64 = 0x0101_0101_0101_0101;
+const HI_BITS: u64 = 0x8080_8080_8080_8080;
+
+// Top seven bits of the hash; the low bits pick the starting bucket.
+fn h2(hash: u64) -> u8 {
+    (hash >> 57) as u8
+}
+
+#[derive(Clone, Copy)]
+struct Group(u64);
+
+impl Group {
+    unsafe fn load(ctrl: *const u8) -> Self {
+        Group(u64::from_le(ptr::read_unaligned(ctrl as *const u64)))
+    }
+
+    // Bytes equal to `byte`. A byte just above a real match can show up as a
+    // false positive, but only when it is full, so callers comparing keys
+    // never read an empty bucket.
+    fn match_byte(self, byte: u8) -> BitMask {
+        let cmp = self.0 ^ (LO_BITS * byte as u64);
+        BitMask(cmp.wrapping_sub(LO_BITS) & !cmp & HI_BITS)
+    }
+
+    // EMPTY is the only control byte with its top two bits set.
+    fn match_empty(self) -> BitMask {
+        BitMask(self.0 & (self.0 << 1) & HI_BITS)
+    }
+
+    fn match_empty_or_deleted(self) -> BitMask {
+        BitMask(self.0 & HI_BITS)
+    }
+
+    fn match_full(self) -> BitMask {
+        BitMask(!self.0 & HI_BITS)
+    }
+}
+
+// The high bit of each matching byte in a group, lowest bucket first.
+struct BitMask(u64);
+
+impl BitMask {
+    fn any(&self) -> bool {
+        self.0 != 0
+    }
+
+    fn lowest(&self) -> Option<usize> {
+        if self.0 == 0 {
+            None
+        } else {
+            Some(self.0.trailing_zeros() as usize / 8)
+        }
+    }
+
+    fn leading_zeros(&self) -> usize {
+        self.0.leading_zeros() as usize / 8
+    }
+
+    fn trailing_zeros(&self) -> usize {
+        self.0.trailing_zeros() as usize / 8
+    }
+}
+
+impl Iterator for BitMask {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let bit = self.lowest()?;
+        self.0 &= self.0 - 1;
+        Some(bit)
+    }
+}
+
+// Triangular probing over groups, which visits every group exactly once
+// when the bucket count is a power of two.
+struct ProbeSeq {
+    pos: usize,
+    stride: usize,
+}
+
+impl ProbeSeq {
+    fn new(hash: u64, bucket_mask: usize) -> Self {
+        ProbeSeq {
+            pos: hash as usize & bucket_mask,
+            stride: 0,
+        }
+    }
+
+    fn move_next(&mut self, bucket_mask: usize) {
+        self.stride += GROUP_WIDTH;
+        self.pos = (self.pos + self.stride) & bucket_mask;
+    }
+}
+
+// Tables are kept at most 7/8 full so every probe ends at an empty bucket.
+pub(crate) fn bucket_capacity(buckets: usize) -> usize {
+    buckets / 8 * 7
+}
+
+pub(crate) fn buckets_for_capacity(cap: usize) -> usize {
+    let mut buckets = cap.max(GROUP_WIDTH).next_power_of_two();
+    while bucket_capacity(buckets) < cap {
+        buckets *= 2;
+    }
+    buckets
+}
+
+pub(crate) struct RawTable<K, V> {
+    ctrl: Box<[u8]>,
+    slots: Box<[MaybeUninit<(K, V)>]>,
+    bucket_mask: usize,
+    items: usize,
+    // Inserts left before the table must grow. Filling a DELETED bucket does
+    // not use any up, so tombstones count against it until a rehash.
+    growth_left: usize,
+}
+
+impl<K, V> RawTable<K, V> {
+    pub(crate) fn with_buckets(buckets: usize) -> Self {
+        debug_assert!(buckets.is_power_of_two() && buckets >= GROUP_WIDTH);
+        let mut slots = Vec::with_capacity(buckets);
+        slots.resize_with(buckets, MaybeUninit::uninit);
+        RawTable {
+            ctrl: vec![EMPTY; buckets + GROUP_WIDTH].into_boxed_slice(),
+            slots: slots.into_boxed_slice(),
+            bucket_mask: buckets - 1,
+            items: 0,
+            growth_left: bucket_capacity(buckets),
+        }
+    }
+
+    pub(crate) fn buckets(&self) -> usize {
+        self.bucket_mask + 1
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.items
+    }
+
+    pub(crate) fn growth_left(&self) -> usize {
+        self.growth_left
+    }
+
+    fn set_ctrl(&mut self, index: usize, ctrl: u8) {
+        // Buckets in the first group are written twice, once at the mirror.
+        let mirror = (index.wrapping_sub(GROUP_WIDTH) & self.bucket_mask) + GROUP_WIDTH;
+        self.ctrl[index] = ctrl;
+        self.ctrl[mirror] = ctrl;
+    }
+
+    fn load_group(&self, pos: usize) -> Group {
+        unsafe { Group::load(self.ctrl.as_ptr().add(pos)) }
+    }
+
+    // The bucket must be full.
+    pub(crate) unsafe fn slot(&self, index: usize) -> &(K, V) {
+        self.slots[index].assume_init_ref()
+    }
+
+    pub(crate) unsafe fn slot_mut(&mut self, index: usize) -> &mut (K, V) {
+        self.slots[index].assume_init_mut()
+    }
+
+    pub(crate) fn find<F>(&self, hash: u64, mut eq: F) -> Option<usize>
+    where
+        F: FnMut(&K) -> bool,
+    {
+        let h2 = h2(hash);
+        let mut probe = ProbeSeq::new(hash, self.bucket_mask);
+        loop {
+            let group = self.load_group(probe.pos);
+            for bit in group.match_byte(h2) {
+                let index = (probe.pos + bit) & self.bucket_mask;
+                if eq(unsafe { &self.slot(index).0 }) {
+                    return Some(index);
+                }
+            }
+            if group.match_empty().any() {
+                return None;
+            }
+            probe.move_next(self.bucket_mask);
+        }
+    }
+
+    // First EMPTY or DELETED bucket on the probe sequence for `hash`, and
+    // the number of groups probed to reach it.
+    pub(crate) fn find_insert_slot(&self, hash: u64) -> (usize, usize) {
+        let mut probe = ProbeSeq::new(hash, self.bucket_mask);
+        let mut groups = 1;
+        loop {
+            if let Some(bit) = self.load_group(probe.pos).match_empty_or_deleted().lowest() {
+                return ((probe.pos + bit) & self.bucket_mask, groups);
+            }
+            probe.move_next(self.bucket_mask);
+            groups += 1;
+        }
+    }
+
+    // Calls `f` with each full bucket on the probe sequence starting at
+    // bucket `home`, up to the group where lookups would stop. Every entry
+    // whose hash starts its probe at `home` is among them.
+    pub(crate) fn for_each_probed<F>(&self, home: usize, mut f: F)
+    where
+        F: FnMut(usize),
+    {
+        let mut probe = ProbeSeq::new(home as u64, self.bucket_mask);
+        loop {
+            let group = self.load_group(probe.pos);
+            for bit in group.match_full() {
+                f((probe.pos + bit) & self.bucket_mask);
+            }
+            if group.match_empty().any() {
+                return;
+            }
+            probe.move_next(self.bucket_mask);
+        }
+    }
+
+    pub(crate) fn is_empty_slot(&self, index: usize) -> bool {
+        self.ctrl[index] == EMPTY
+    }
+
+    // `index` must come from `find_insert_slot` for the same hash, with
+    // growth left if the bucket is EMPTY.
+    pub(crate) unsafe fn insert_at(&mut self, index: usize, hash: u64, key: K, value: V) {
+        self.growth_left -= self.is_empty_slot(index) as usize;
+        self.set_ctrl(index, h2(hash));
+        self.slots[index].write((key, value));
+        self.items += 1;
+    }
+
+    // Inserts a key known to be absent into a table with growth left.
+    pub(crate) fn insert_unique(&mut self, hash: u64, key: K, value: V) -> usize {
+        let (index, _) = self.find_insert_slot(hash);
+        unsafe { self.insert_at(index, hash, key, value) };
+        index
+    }
+
+    // Moves the entry out of a full bucket. The bucket becomes EMPTY again
+    // when no probe can have passed over it, which is the case whenever the
+    // run of full buckets around it is shorter than a group.
+    pub(crate) unsafe fn take(&mut self, index: usize) -> (K, V) {
+        let before = self.load_group(index.wrapping_sub(GROUP_WIDTH) & self.bucket_mask);
+        let after = self.load_group(index);
+        let run = before.match_empty().leading_zeros() + after.match_empty().trailing_zeros();
+        let ctrl = if run >= GROUP_WIDTH {
+            DELETED
+        } else {
+            self.growth_left += 1;
+            EMPTY
+        };
+        self.set_ctrl(index, ctrl);
+        self.items -= 1;
+        self.slots[index].assume_init_read()
+    }
+
+    // Next full bucket at or after `index`.
+    pub(crate) fn next_full(&self, mut index: usize) -> Option<usize> {
+        while index < self.buckets() {
+            if let Some(bit) = self.load_group(index).match_full().lowest() {
+                let found = index + bit;
+                // Past the end the mirrored bytes repeat the first group.
+                return if found < self.buckets() { Some(found) } else { None };
+            }
+            index += GROUP_WIDTH;
+        }
+        None
+    }
+
+    // Checks the bookkeeping that needs no hashes: the mirrored control
+    // bytes, the item count and the growth left.
+    pub(crate) fn validate_ctrl(&self) -> Result<(), CroInvariantError> {
+        let buckets = self.buckets();
+        for bucket in 0..GROUP_WIDTH {
+            if self.ctrl[buckets + bucket] != self.ctrl[bucket] {
+                return Err(CroInvariantError::MirrorMismatch { bucket });
+            }
+        }
+        let ctrl = &self.ctrl[..buckets];
+        let full = ctrl.iter().filter(|&&byte| byte & 0x80 == 0).count();
+        let deleted = ctrl.iter().filter(|&&byte| byte == DELETED).count();
+        if full != self.items {
+            return Err(CroInvariantError::LenMismatch {
+                stored: self.items,
+                counted: full,
+            });
+        }
+        let expected = bucket_capacity(buckets).saturating_sub(full + deleted);
+        if self.growth_left != expected {
+            return Err(CroInvariantError::GrowthMismatch {
+                stored: self.growth_left,
+                expected,
+            });
+        }
+        Ok(())
+    }
+
+    // Checks every entry against `hash`: its control byte holds the hash
+    // fragment, and no group its probe passes through on the way to it has
+    // an EMPTY bucket to stop a lookup early.
+    pub(crate) fn validate_entries<F>(&self, mut hash: F) -> Result<(), CroInvariantError>
+    where
+        F: FnMut(&K, &V) -> u64,
+    {
+        let mut index = 0;
+        while let Some(bucket) = self.next_full(index) {
+            let (key, value) = unsafe { self.slot(bucket) };
+            let hash = hash(key, value);
+            if self.ctrl[bucket] != h2(hash) {
+                return Err(CroInvariantError::HashMismatch { bucket });
+            }
+            let mut probe = ProbeSeq::new(hash, self.bucket_mask);
+            while bucket.wrapping_sub(probe.pos) & self.bucket_mask >= GROUP_WIDTH {
+                if self.load_group(probe.pos).match_empty().any() {
+                    return Err(CroInvariantError::UnreachableEntry { bucket });
+                }
+                probe.move_next(self.bucket_mask);
+            }
+            index = bucket + 1;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.drop_entries();
+        self.ctrl.fill(EMPTY);
+        self.items = 0;
+        self.growth_left = bucket_capacity(self.buckets());
+    }
+
+    fn drop_entries(&mut self) {
+        if !mem::needs_drop::<(K, V)>() || self.items == 0 {
+            return;
+        }
+        let mut index = 0;
+        while let Some(full) = self.next_full(index) {
+            unsafe { self.slots[full].assume_init_drop() };
+            index = full + 1;
+        }
+    }
+}
+
+impl<K, V> Drop for RawTable<K, V> {
+    fn drop(&mut self) {
+        self.drop_entries();
+    }
+}
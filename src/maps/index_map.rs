@@ -0,0 +1,803 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
+use std::mem;
+use std::slice;
+
+use super::hash_table::{bucket_capacity, buckets_for_capacity, RawTable};
+use crate::invariants::{check_invariants, CroInvariantError};
+use crate::sequences::vec::CroVecIntoIter;
+use crate::sequences::CroVec;
+
+// Entries sit densely in `entries` in insertion order. `indices` is a
+// `CroMap`-style table from each key's hash to its position in `entries`,
+// with the hash kept next to the position so the table can grow without
+// hashing any key again.
+pub struct CroIndexMap<K, V, S = RandomState> {
+    entries: CroVec<(K, V)>,
+    indices: RawTable<usize, u64>,
+    build_hasher: S,
+}
+
+pub enum CroIndexMapEntry<'a, K, V, S = RandomState> {
+    Occupied(CroIndexMapOccupiedEntry<'a, K, V, S>),
+    Vacant(CroIndexMapVacantEntry<'a, K, V, S>),
+}
+
+pub struct CroIndexMapOccupiedEntry<'a, K, V, S = RandomState> {
+    map: &'a mut CroIndexMap<K, V, S>,
+    // Bucket in `indices` that holds `index`.
+    bucket: usize,
+    index: usize,
+}
+
+pub struct CroIndexMapVacantEntry<'a, K, V, S = RandomState> {
+    map: &'a mut CroIndexMap<K, V, S>,
+    hash: u64,
+    key: K,
+}
+
+pub struct CroIndexMapIter<'a, K, V> {
+    iter: slice::Iter<'a, (K, V)>,
+}
+
+pub struct CroIndexMapIterMut<'a, K, V> {
+    iter: slice::IterMut<'a, (K, V)>,
+}
+
+pub struct CroIndexMapIntoIter<K, V> {
+    iter: CroVecIntoIter<(K, V)>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for CroIndexMapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for CroIndexMapIter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+impl<'a, K, V> Iterator for CroIndexMapIterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(key, value)| (&*key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for CroIndexMapIterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(key, value)| (&*key, value))
+    }
+}
+
+impl<K, V> Iterator for CroIndexMapIntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.iter.next()?;
+        self.remaining -= 1;
+        Some(entry)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for CroIndexMapIter<'_, K, V> {}
+
+impl<K, V> ExactSizeIterator for CroIndexMapIterMut<'_, K, V> {}
+
+impl<K, V> ExactSizeIterator for CroIndexMapIntoIter<K, V> {}
+
+impl<K, V> CroIndexMap<K, V>
+where
+    K: Hash + Eq,
+{
+    pub fn new() -> Self {
+        CroIndexMap::with_cap_and_hasher(0, RandomState::new())
+    }
+}
+
+impl<K, V, S> CroIndexMap<K, V, S> {
+    pub fn size(&self) -> usize {
+        self.entries.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn cap(&self) -> usize {
+        self.entries.cap()
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.build_hasher
+    }
+
+    pub fn iter(&self) -> CroIndexMapIter<'_, K, V> {
+        CroIndexMapIter {
+            iter: self.entries.as_slice().iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> CroIndexMapIterMut<'_, K, V> {
+        CroIndexMapIterMut {
+            iter: self.entries.as_mut_slice().iter_mut(),
+        }
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        self.entries.get(index).map(|(key, value)| (key, value))
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
+        self.entries.get_mut(index).map(|(key, value)| (&*key, value))
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.entries.first().map(|(key, value)| (key, value))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.entries.last().map(|(key, value)| (key, value))
+    }
+
+    pub fn wipe(&mut self) {
+        self.entries.clear();
+        self.indices.clear();
+        check_invariants!(self.validate_indices());
+    }
+
+    // Moves the entry at `from` to `to`, shifting the entries in between by
+    // one place.
+    pub fn move_index(&mut self, from: usize, to: usize) {
+        assert!(from < self.size() && to < self.size(), "index out of bounds");
+        if from < to {
+            self.update_indices(|index| {
+                if index == from {
+                    to
+                } else if index > from && index <= to {
+                    index - 1
+                } else {
+                    index
+                }
+            });
+            self.entries.as_mut_slice()[from..=to].rotate_left(1);
+        } else if to < from {
+            self.update_indices(|index| {
+                if index == from {
+                    to
+                } else if index >= to && index < from {
+                    index + 1
+                } else {
+                    index
+                }
+            });
+            self.entries.as_mut_slice()[to..=from].rotate_right(1);
+        }
+        check_invariants!(self.validate_indices());
+    }
+
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let entries = &self.entries;
+        self.indices.find(hash, |&index| entries[index].0.borrow() == key)
+    }
+
+    fn index_at(&self, bucket: usize) -> usize {
+        unsafe { self.indices.slot(bucket).0 }
+    }
+
+    fn update_indices<F>(&mut self, mut f: F)
+    where
+        F: FnMut(usize) -> usize,
+    {
+        let mut bucket = 0;
+        while let Some(full) = self.indices.next_full(bucket) {
+            let slot = unsafe { self.indices.slot_mut(full) };
+            slot.0 = f(slot.0);
+            bucket = full + 1;
+        }
+    }
+
+    // Records that the entry with `hash` now lives at the end of `entries`.
+    fn push_index(&mut self, hash: u64) {
+        let index = self.entries.size();
+        let (bucket, _) = self.indices.find_insert_slot(hash);
+        if self.indices.growth_left() == 0 && self.indices.is_empty_slot(bucket) {
+            self.grow_indices(1);
+            self.indices.insert_unique(hash, index, hash);
+        } else {
+            unsafe { self.indices.insert_at(bucket, hash, index, hash) };
+        }
+    }
+
+    // Rebuilds the index table with room for `additional` more entries, at
+    // the same size when tombstones rather than entries filled it.
+    fn grow_indices(&mut self, additional: usize) {
+        let required = self.entries.size() + additional;
+        let buckets = self.indices.buckets();
+        let target = if required <= bucket_capacity(buckets) / 2 {
+            buckets
+        } else {
+            buckets_for_capacity(required.max(bucket_capacity(buckets) + 1))
+        };
+        let mut old = mem::replace(&mut self.indices, RawTable::with_buckets(target));
+        let mut bucket = 0;
+        while let Some(full) = old.next_full(bucket) {
+            let (index, hash) = unsafe { old.take(full) };
+            self.indices.insert_unique(hash, index, hash);
+            bucket = full + 1;
+        }
+    }
+
+    fn shift_remove_bucket(&mut self, bucket: usize) -> (usize, K, V) {
+        let (index, _) = unsafe { self.indices.take(bucket) };
+        let (key, value) = self.entries.remove(index);
+        if index < self.entries.size() {
+            self.update_indices(|i| if i > index { i - 1 } else { i });
+        }
+        check_invariants!(self.validate_indices());
+        (index, key, value)
+    }
+
+    // The part of `validate` that needs no hashing: the index table's own
+    // bookkeeping against the hashes it stores, and that it holds every
+    // position in `entries` exactly once.
+    fn validate_indices(&self) -> Result<(), CroInvariantError> {
+        self.entries.validate()?;
+        self.indices.validate_ctrl()?;
+        self.indices.validate_entries(|_, &hash| hash)?;
+        if self.indices.len() != self.entries.size() {
+            return Err(CroInvariantError::LenMismatch {
+                stored: self.entries.size(),
+                counted: self.indices.len(),
+            });
+        }
+        let mut seen = CroVec::new();
+        seen.resize(self.entries.size(), false);
+        let mut bucket = 0;
+        while let Some(full) = self.indices.next_full(bucket) {
+            let index = self.index_at(full);
+            if index >= seen.size() || seen[index] {
+                return Err(CroInvariantError::BadIndex { index });
+            }
+            seen[index] = true;
+            bucket = full + 1;
+        }
+        Ok(())
+    }
+}
+
+impl<K, V, S> CroIndexMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn with_hasher(build_hasher: S) -> Self {
+        Self::with_cap_and_hasher(0, build_hasher)
+    }
+
+    pub fn with_cap(cap: usize) -> Self
+    where
+        S: Default,
+    {
+        Self::with_cap_and_hasher(cap, S::default())
+    }
+
+    pub fn with_cap_and_hasher(cap: usize, build_hasher: S) -> Self {
+        CroIndexMap {
+            entries: CroVec::with_cap(cap),
+            indices: RawTable::with_buckets(buckets_for_capacity(cap)),
+            build_hasher,
+        }
+    }
+
+    // Also checks that each stored hash is the hash of the key it indexes.
+    pub fn validate(&self) -> Result<(), CroInvariantError> {
+        self.validate_indices()?;
+        let mut bucket = 0;
+        while let Some(full) = self.indices.next_full(bucket) {
+            let (index, hash) = unsafe { *self.indices.slot(full) };
+            if self.hash(&self.entries[index].0) != hash {
+                return Err(CroInvariantError::HashMismatch { bucket: full });
+            }
+            bucket = full + 1;
+        }
+        Ok(())
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_full(key, value).1
+    }
+
+    // An existing key keeps its position.
+    pub fn insert_full(&mut self, key: K, value: V) -> (usize, Option<V>) {
+        match self.entry(key) {
+            CroIndexMapEntry::Occupied(mut entry) => (entry.index(), Some(entry.insert(value))),
+            CroIndexMapEntry::Vacant(entry) => {
+                let index = entry.index();
+                entry.insert(value);
+                (index, None)
+            }
+        }
+    }
+
+    pub fn entry(&mut self, key: K) -> CroIndexMapEntry<'_, K, V, S> {
+        let hash = self.hash(&key);
+        match self.find(hash, &key) {
+            Some(bucket) => {
+                let index = self.index_at(bucket);
+                CroIndexMapEntry::Occupied(CroIndexMapOccupiedEntry {
+                    map: self,
+                    bucket,
+                    index,
+                })
+            }
+            None => CroIndexMapEntry::Vacant(CroIndexMapVacantEntry {
+                map: self,
+                hash,
+                key,
+            }),
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_full(key).map(|(_, _, value)| value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.get_index_of(key)?;
+        Some(&mut self.entries[index].1)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_full(key).map(|(_, key, value)| (key, value))
+    }
+
+    pub fn get_full<Q>(&self, key: &Q) -> Option<(usize, &K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.get_index_of(key)?;
+        let (key, value) = &self.entries[index];
+        Some((index, key, value))
+    }
+
+    pub fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let bucket = self.find(self.hash(key), key)?;
+        Some(self.index_at(bucket))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_index_of(key).is_some()
+    }
+
+    // Fills the gap with the last entry, so O(1) but perturbs the order.
+    pub fn swap_remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.swap_remove_full(key).map(|(_, _, value)| value)
+    }
+
+    pub fn swap_remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.swap_remove_full(key).map(|(_, key, value)| (key, value))
+    }
+
+    pub fn swap_remove_full<Q>(&mut self, key: &Q) -> Option<(usize, K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let bucket = self.find(self.hash(key), key)?;
+        Some(self.swap_remove_bucket(bucket))
+    }
+
+    pub fn swap_remove_index(&mut self, index: usize) -> Option<(K, V)> {
+        let bucket = self.bucket_of(index)?;
+        let (_, key, value) = self.swap_remove_bucket(bucket);
+        Some((key, value))
+    }
+
+    // Shifts every later entry down by one, keeping the order at O(n) cost.
+    pub fn shift_remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shift_remove_full(key).map(|(_, _, value)| value)
+    }
+
+    pub fn shift_remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shift_remove_full(key).map(|(_, key, value)| (key, value))
+    }
+
+    pub fn shift_remove_full<Q>(&mut self, key: &Q) -> Option<(usize, K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let bucket = self.find(self.hash(key), key)?;
+        Some(self.shift_remove_bucket(bucket))
+    }
+
+    pub fn shift_remove_index(&mut self, index: usize) -> Option<(K, V)> {
+        let bucket = self.bucket_of(index)?;
+        let (_, key, value) = self.shift_remove_bucket(bucket);
+        Some((key, value))
+    }
+
+    pub fn pop(&mut self) -> Option<(K, V)> {
+        let last = self.size().checked_sub(1)?;
+        self.swap_remove_index(last)
+    }
+
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        let size = self.entries.size();
+        let entries = mem::replace(&mut self.entries, CroVec::with_cap(size));
+        for (key, mut value) in entries {
+            if f(&key, &mut value) {
+                self.entries.push((key, value));
+            }
+        }
+        self.rebuild_indices();
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.entries.reserve(additional);
+        if additional > self.indices.growth_left() {
+            self.grow_indices(additional);
+        }
+        check_invariants!(self.validate());
+    }
+
+    pub fn sort_keys(&mut self)
+    where
+        K: Ord,
+    {
+        self.sort_by(|k1, _, k2, _| k1.cmp(k2));
+    }
+
+    // Stable, so entries that compare equal keep their relative order.
+    pub fn sort_by<F>(&mut self, mut cmp: F)
+    where
+        F: FnMut(&K, &V, &K, &V) -> Ordering,
+    {
+        self.entries.as_mut_slice().sort_by(|(k1, v1), (k2, v2)| cmp(k1, v1, k2, v2));
+        self.rebuild_indices();
+    }
+
+    pub fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+
+    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        self.build_hasher.hash_one(key)
+    }
+
+    fn bucket_of(&self, index: usize) -> Option<usize> {
+        let (key, _) = self.entries.get(index)?;
+        self.indices.find(self.hash(key), |&i| i == index)
+    }
+
+    fn swap_remove_bucket(&mut self, bucket: usize) -> (usize, K, V) {
+        let (index, _) = unsafe { self.indices.take(bucket) };
+        let last = self.entries.size() - 1;
+        if index != last {
+            let moved = self.bucket_of(last).unwrap();
+            unsafe { self.indices.slot_mut(moved).0 = index };
+        }
+        let (key, value) = self.entries.swap_remove(index);
+        check_invariants!(self.validate());
+        (index, key, value)
+    }
+
+    // Reindexes every entry after their positions have been rearranged.
+    fn rebuild_indices(&mut self) {
+        self.indices.clear();
+        if self.indices.growth_left() < self.entries.size() {
+            self.indices = RawTable::with_buckets(buckets_for_capacity(self.entries.size()));
+        }
+        for index in 0..self.entries.size() {
+            let hash = self.hash(&self.entries[index].0);
+            self.indices.insert_unique(hash, index, hash);
+        }
+        check_invariants!(self.validate());
+    }
+}
+
+impl<'a, K, V, S> CroIndexMapEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        match self {
+            CroIndexMapEntry::Occupied(entry) => entry.key(),
+            CroIndexMapEntry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        match self {
+            CroIndexMapEntry::Occupied(entry) => entry.index(),
+            CroIndexMapEntry::Vacant(entry) => entry.index(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            CroIndexMapEntry::Occupied(entry) => entry.into_mut(),
+            CroIndexMapEntry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            CroIndexMapEntry::Occupied(entry) => entry.into_mut(),
+            CroIndexMapEntry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        if let CroIndexMapEntry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K, V, S> CroIndexMapOccupiedEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        &self.map.entries[self.index].0
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn get(&self) -> &V {
+        &self.map.entries[self.index].1
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.map.entries[self.index].1
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.map.entries[self.index].1
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn shift_remove_entry(self) -> (K, V) {
+        let (_, key, value) = self.map.shift_remove_bucket(self.bucket);
+        (key, value)
+    }
+
+    pub fn shift_remove(self) -> V {
+        self.shift_remove_entry().1
+    }
+}
+
+impl<'a, K, V, S> CroIndexMapOccupiedEntry<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn swap_remove_entry(self) -> (K, V) {
+        let (_, key, value) = self.map.swap_remove_bucket(self.bucket);
+        (key, value)
+    }
+
+    pub fn swap_remove(self) -> V {
+        self.swap_remove_entry().1
+    }
+}
+
+impl<'a, K, V, S> CroIndexMapVacantEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    // Where the entry will go: new keys are appended.
+    pub fn index(&self) -> usize {
+        self.map.size()
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        let map = self.map;
+        map.push_index(self.hash);
+        map.entries.push((self.key, value));
+        check_invariants!(map.validate_indices());
+        &mut map.entries.last_mut().unwrap().1
+    }
+}
+
+impl<K, V, S> Default for CroIndexMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: Default + BuildHasher,
+{
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K, V, S> Clone for CroIndexMap<K, V, S>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher + Clone,
+{
+    fn clone(&self) -> Self {
+        let mut next = CroIndexMap::with_cap_and_hasher(self.size(), self.build_hasher.clone());
+        next.entries = self.entries.clone();
+        next.rebuild_indices();
+        next
+    }
+}
+
+impl<K, V, S> fmt::Debug for CroIndexMap<K, V, S>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// As with `indexmap`, equality ignores the order of the entries.
+impl<K, V, S> PartialEq for CroIndexMap<K, V, S>
+where
+    K: Hash + Eq,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.size() == other.size()
+            && self.iter().all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K, V, S> Eq for CroIndexMap<K, V, S>
+where
+    K: Hash + Eq,
+    V: Eq,
+    S: BuildHasher,
+{
+}
+
+impl<K, V, S> FromIterator<(K, V)> for CroIndexMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: Default + BuildHasher,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = CroIndexMap::with_hasher(S::default());
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V, S> Extend<(K, V)> for CroIndexMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        CroIndexMap::extend(self, iter);
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a CroIndexMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = CroIndexMapIter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut CroIndexMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = CroIndexMapIterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, V, S> IntoIterator for CroIndexMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = CroIndexMapIntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let remaining = self.entries.size();
+        CroIndexMapIntoIter {
+            iter: self.entries.into_iter(),
+            remaining,
+        }
+    }
+}
@@ -1,4 +1,18 @@
 mod hash_map;
-pub use hash_map::{CroMap, CroMapDrain, CroMapIntoIter, CroMapIter};
+mod hash_table;
+mod index_map;
+pub use index_map::{
+    CroIndexMap, CroIndexMapEntry, CroIndexMapIntoIter, CroIndexMapIter, CroIndexMapIterMut,
+    CroIndexMapOccupiedEntry, CroIndexMapVacantEntry,
+};
+pub use hash_map::{
+    CroMap, CroMapDrain, CroMapEntry, CroMapIntoIter, CroMapIter, CroMapOccupiedEntry,
+    CroMapRawEntryBuilder, CroMapRawEntryBuilderMut, CroMapRawEntryMut, CroMapRawOccupiedEntry,
+    CroMapRawVacantEntry, CroMapStats, CroMapVacantEntry,
+};
 mod btree_map;
-pub use btree_map::{CroBTree, CroBTreeIter};
+mod btree_node;
+pub use btree_map::{
+    CroBTree, CroBTreeCursor, CroBTreeCursorMut, CroBTreeEntry, CroBTreeIntoIter, CroBTreeIter,
+    CroBTreeOccupiedEntry, CroBTreeRange, CroBTreeRangeMut, CroBTreeVacantEntry, Summary,
+};
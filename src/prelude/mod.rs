@@ -0,0 +1,10 @@
+// Everything a typical user needs in one glob import. The std-named methods
+// are opt-in through `std_compat`.
+pub use crate::cmp::{Comparator, CroNaturalOrder, CroReverseOrder};
+pub use crate::hash::{
+    CroDetMap, CroDetSet, CroDetState, CroFxMap, CroFxSet, CroFxState, CroSipState,
+};
+pub use crate::maps::{CroBTree, CroBTreeEntry, CroIndexMap, CroIndexMapEntry, CroMap, CroMapEntry};
+pub use crate::sequences::{CroLList, CroQue, CroVec};
+pub use crate::sets::{CroBTreeSet, CroHashSet, CroIndexSet};
+pub use crate::traits::{Collection, MapEntry, MapLike, OrderedMapLike, Sequence, SetLike};
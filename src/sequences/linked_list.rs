@@ -1,6 +1,9 @@
+use std::collections::LinkedList;
 use std::rc::Rc;
 use std::cell::RefCell;
 
+use crate::invariants::{check_invariants, CroInvariantError};
+
 #[derive(Debug)]
 pub struct Node<T> {
     data: T,
@@ -42,6 +45,48 @@ impl<T> CroLList<T> {
         self.size == 0
     }
 
+    // Walks from the head, checking that each node's `prev` points at the
+    // node before it and that the walk ends at `tail` after `size` nodes.
+    pub fn validate(&self) -> Result<(), CroInvariantError> {
+        let mut prev: Option<Rc<RefCell<Node<T>>>> = None;
+        let mut next = self.head.clone();
+        let mut index = 0;
+        while let Some(node) = next {
+            if index == self.size {
+                return Err(CroInvariantError::LenMismatch {
+                    stored: self.size,
+                    counted: index + 1,
+                });
+            }
+            let linked = match (&node.borrow().prev, &prev) {
+                (Some(back), Some(prev)) => Rc::ptr_eq(back, prev),
+                (None, None) => true,
+                _ => false,
+            };
+            if !linked {
+                return Err(CroInvariantError::BrokenLink { index });
+            }
+            next = node.borrow().next.clone();
+            prev = Some(node);
+            index += 1;
+        }
+        if index != self.size {
+            return Err(CroInvariantError::LenMismatch {
+                stored: self.size,
+                counted: index,
+            });
+        }
+        let tail_ok = match (&self.tail, &prev) {
+            (Some(tail), Some(last)) => Rc::ptr_eq(tail, last),
+            (None, None) => true,
+            _ => false,
+        };
+        if !tail_ok {
+            return Err(CroInvariantError::BrokenLink { index });
+        }
+        Ok(())
+    }
+
     pub fn push_front(&mut self, data: T) {
         let new_node = Rc::new(RefCell::new(Node::new(data)));
 
@@ -58,6 +103,7 @@ impl<T> CroLList<T> {
         }
 
         self.size += 1;
+        check_invariants!(self.validate());
     }
 
     pub fn push_back(&mut self, data: T) {
@@ -76,6 +122,7 @@ impl<T> CroLList<T> {
         }
 
         self.size += 1;
+        check_invariants!(self.validate());
     }
 
     pub fn pop_front(&mut self) -> Option<T> {
@@ -92,11 +139,13 @@ impl<T> CroLList<T> {
                 }
             }
 
-            Rc::try_unwrap(old_head)
+            let data = Rc::try_unwrap(old_head)
                 .ok()
                 .unwrap()
                 .into_inner()
-                .data
+                .data;
+            check_invariants!(self.validate());
+            data
         })
     }
 
@@ -114,11 +163,13 @@ impl<T> CroLList<T> {
                 }
             }
 
-            Rc::try_unwrap(old_tail)
+            let data = Rc::try_unwrap(old_tail)
                 .ok()
                 .unwrap()
                 .into_inner()
-                .data
+                .data;
+            check_invariants!(self.validate());
+            data
         })
     }
 
@@ -135,6 +186,12 @@ impl<T> CroLList<T> {
     }
 }
 
+impl<T> Default for CroLList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Drop for CroLList<T> {
     fn drop(&mut self) {
         self.wipe();
@@ -158,4 +215,20 @@ impl<T> IntoIterator for CroLList<T> {
     fn into_iter(self) -> Self::IntoIter {
         IntoIter(self)
     }
-}
\ No newline at end of file
+}
+
+impl<T> From<LinkedList<T>> for CroLList<T> {
+    fn from(list: LinkedList<T>) -> Self {
+        let mut out = CroLList::new();
+        for value in list {
+            out.push_back(value);
+        }
+        out
+    }
+}
+
+impl<T> From<CroLList<T>> for LinkedList<T> {
+    fn from(list: CroLList<T>) -> Self {
+        list.into_iter().collect()
+    }
+}
@@ -5,6 +5,9 @@ use std::marker::PhantomData;
 use std::ops::{Bound, Index, IndexMut, RangeBounds};
 use std::slice;
 
+use crate::cmp::Comparator;
+use crate::invariants::{check_invariants, CroInvariantError};
+
 #[derive(Debug)]
 pub struct CroVec<T> {
     pub(crate) data: *mut T,
@@ -75,6 +78,19 @@ impl<T> CroVec<T> {
         self.size == 0
     }
 
+    pub fn validate(&self) -> Result<(), CroInvariantError> {
+        if self.size > self.cap {
+            return Err(CroInvariantError::SizeExceedsCap {
+                size: self.size,
+                cap: self.cap,
+            });
+        }
+        if self.cap > 0 && self.data.is_null() {
+            return Err(CroInvariantError::MissingBuffer { cap: self.cap });
+        }
+        Ok(())
+    }
+
     pub fn get(&self, index: usize) -> Option<&T> {
         if index < self.size {
             unsafe { Some(&*self.data.add(index)) }
@@ -135,6 +151,7 @@ impl<T> CroVec<T> {
         }
 
         self.size += 1;
+        check_invariants!(self.validate());
     }
 
     pub fn push(&mut self, value: T) {
@@ -146,6 +163,7 @@ impl<T> CroVec<T> {
             std::ptr::write(self.data.add(self.size), value);
         }
         self.size += 1;
+        check_invariants!(self.validate());
     }
 
     pub fn pop(&mut self) -> Option<T> {
@@ -153,6 +171,7 @@ impl<T> CroVec<T> {
             None
         } else {
             self.size -= 1;
+            check_invariants!(self.validate());
             unsafe {
                 Some(std::ptr::read(self.data.add(self.size)))
             }
@@ -222,6 +241,7 @@ impl<T> CroVec<T> {
         self.size = len;
 
         debug_assert!(self.size <= self.cap);
+        check_invariants!(self.validate());
     }
 
     pub fn swap(&mut self, a: usize, b: usize) {
@@ -242,7 +262,6 @@ impl<T> CroVec<T> {
     pub fn remove(&mut self, index: usize) -> T {
         assert!(index < self.size, "Index out of bounds");
 
-        let old_size = self.size;
         let value = unsafe { std::ptr::read(self.data.add(index)) };
 
         unsafe {
@@ -256,12 +275,7 @@ impl<T> CroVec<T> {
         }
 
         self.size -= 1;
-        if index < old_size - 1 {
-            unsafe {
-                std::ptr::drop_in_place(self.data.add(self.size));
-            }
-        }
-
+        check_invariants!(self.validate());
         value
     }
 
@@ -276,6 +290,7 @@ impl<T> CroVec<T> {
             std::ptr::write(self.data.add(index), last);
         }
         self.size -= 1;
+        check_invariants!(self.validate());
         value
     }
 
@@ -292,6 +307,7 @@ impl<T> CroVec<T> {
         }
         self.size += other.size;
         other.size = 0;
+        check_invariants!(self.validate());
     }
 
     pub fn split_off(&mut self, at: usize) -> CroVec<T> {
@@ -304,6 +320,7 @@ impl<T> CroVec<T> {
             }
         }
         self.size = at;
+        check_invariants!(self.validate());
         right
     }
 
@@ -327,6 +344,7 @@ impl<T> CroVec<T> {
             }
         }
         self.size = write;
+        check_invariants!(self.validate());
     }
 
     pub fn dedup(&mut self)
@@ -360,6 +378,7 @@ impl<T> CroVec<T> {
             }
         }
         self.size = write;
+        check_invariants!(self.validate());
     }
 
     pub fn contains(&self, value: &T) -> bool
@@ -423,11 +442,18 @@ impl<T> CroVec<T> {
         }
     }
 
+    // An unallocated vector has a null pointer, which slices may not hold.
     pub fn as_slice(&self) -> &[T] {
+        if self.data.is_null() {
+            return &[];
+        }
         unsafe { slice::from_raw_parts(self.data, self.size) }
     }
 
     pub fn as_mut_slice(&mut self) -> &mut [T] {
+        if self.data.is_null() {
+            return &mut [];
+        }
         unsafe { slice::from_raw_parts_mut(self.data, self.size) }
     }
 
@@ -461,6 +487,22 @@ impl<T> CroVec<T> {
         self.as_slice().binary_search_by_key(b, f)
     }
 
+    // Like `binary_search`, for a vector sorted under `comparator`.
+    pub fn binary_search_with<C>(&self, x: &T, comparator: &C) -> Result<usize, usize>
+    where
+        C: Comparator<T>,
+    {
+        self.as_slice().binary_search_by(|probe| comparator.compare(probe, x))
+    }
+
+    pub fn binary_search_by_key_with<B, F, C>(&self, b: &B, mut f: F, comparator: &C) -> Result<usize, usize>
+    where
+        F: FnMut(&T) -> B,
+        C: Comparator<B>,
+    {
+        self.as_slice().binary_search_by(|probe| comparator.compare(&f(probe), b))
+    }
+
     pub fn drain<R>(&mut self, range: R) -> CroVecIntoIter<T>
     where
         R: RangeBounds<usize>,
@@ -469,7 +511,6 @@ impl<T> CroVec<T> {
         assert!(start <= end, "Invalid drain range");
         assert!(end <= self.size, "Drain range out of bounds");
 
-        let old_size = self.size;
         let mut drained = CroVec::with_cap(end - start);
         for i in start..end {
             unsafe {
@@ -488,11 +529,7 @@ impl<T> CroVec<T> {
             }
         }
         self.size -= end - start;
-        for i in self.size..old_size {
-            unsafe {
-                std::ptr::drop_in_place(self.data.add(i));
-            }
-        }
+        check_invariants!(self.validate());
 
         drained.into_iter()
     }
@@ -511,6 +548,7 @@ impl<T> CroVec<T> {
             }
             self.data = std::ptr::null_mut();
             self.cap = 0;
+            check_invariants!(self.validate());
             return;
         }
 
@@ -527,6 +565,7 @@ impl<T> CroVec<T> {
 
         self.data = new_data;
         self.cap = new_cap;
+        check_invariants!(self.validate());
     }
 }
 
@@ -731,7 +770,7 @@ impl<T> IntoIterator for CroVec<T> {
     type IntoIter = CroVecIntoIter<T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        let mut vec = std::mem::ManuallyDrop::new(self);
+        let vec = std::mem::ManuallyDrop::new(self);
         let data = vec.data;
         let len = vec.size;
         let cap = vec.cap;
@@ -744,6 +783,33 @@ impl<T> IntoIterator for CroVec<T> {
     }
 }
 
+// Both sides allocate `Layout::array::<T>(cap)` from the global allocator, so
+// the buffer changes hands as is. `CroVec` has no buffer for zero-sized types
+// or zero capacity, and those move element by element instead.
+impl<T> From<Vec<T>> for CroVec<T> {
+    fn from(vec: Vec<T>) -> Self {
+        if std::mem::size_of::<T>() == 0 || vec.capacity() == 0 {
+            return vec.into_iter().collect();
+        }
+        let mut vec = std::mem::ManuallyDrop::new(vec);
+        CroVec {
+            data: vec.as_mut_ptr(),
+            size: vec.len(),
+            cap: vec.capacity(),
+        }
+    }
+}
+
+impl<T> From<CroVec<T>> for Vec<T> {
+    fn from(vec: CroVec<T>) -> Self {
+        if std::mem::size_of::<T>() == 0 || vec.data.is_null() {
+            return vec.into_iter().collect();
+        }
+        let vec = std::mem::ManuallyDrop::new(vec);
+        unsafe { Vec::from_raw_parts(vec.data, vec.size, vec.cap) }
+    }
+}
+
 impl<T: PartialOrd> CroVec<T> {
     pub fn sort(&mut self) {
         if self.size <= 1 {
@@ -1,3 +1,6 @@
+use std::collections::VecDeque;
+
+use crate::invariants::{check_invariants, CroInvariantError};
 use crate::sequences::vec::CroVec;
 
 #[derive(Debug)]
@@ -33,6 +36,17 @@ impl<T: std::fmt::Debug> CroQue<T> {
         self.buffer.cap()
     }
 
+    // Elements occupy `head..head + size` of the buffer, and an empty queue
+    // starts over at the front.
+    pub fn validate(&self) -> Result<(), CroInvariantError> {
+        self.buffer.validate()?;
+        let (head, size, cap) = (self.head, self.buffer.size(), self.buffer.cap());
+        if head + size > cap || (size == 0 && head != 0) {
+            return Err(CroInvariantError::HeadOutOfRange { head, size, cap });
+        }
+        Ok(())
+    }
+
     pub fn pop_front(&mut self) -> Option<T> {
         if self.is_empty() {
             None
@@ -57,6 +71,7 @@ impl<T: std::fmt::Debug> CroQue<T> {
             }
 
             self.debug_print("After pop_front");
+            check_invariants!(self.validate());
             value
         }
     }
@@ -77,18 +92,21 @@ impl<T: std::fmt::Debug> CroQue<T> {
         }
         self.buffer.size += 1;
         self.debug_print("After push_front");
+        check_invariants!(self.validate());
     }
 
     pub fn push_back(&mut self, value: T) {
         self.debug_print("Before push_back");
         self.buffer.push(value);
         self.debug_print("After push_back");
+        check_invariants!(self.validate());
     }
 
     pub fn pop_back(&mut self) -> Option<T> {
         self.debug_print("Before pop_back");
         let result = self.buffer.pop();
         self.debug_print("After pop_back");
+        check_invariants!(self.validate());
         result
     }
 
@@ -103,6 +121,7 @@ impl<T: std::fmt::Debug> CroQue<T> {
 
         self.buffer.size = 0;
         self.head = 0;
+        check_invariants!(self.validate());
     }
 
     fn debug_print(&self, operation: &str) {
@@ -119,8 +138,32 @@ impl<T: std::fmt::Debug> CroQue<T> {
     }
 }
 
+impl<T: std::fmt::Debug> Default for CroQue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Drop for CroQue<T> {
     fn drop(&mut self) {
         self.buffer.wipe();
     }
 }
+
+// Elements always start at the front of `buffer`, so both directions go
+// through the `Vec` conversion and keep the allocation.
+impl<T> From<VecDeque<T>> for CroQue<T> {
+    fn from(deque: VecDeque<T>) -> Self {
+        CroQue {
+            buffer: CroVec::from(Vec::from(deque)),
+            head: 0,
+        }
+    }
+}
+
+impl<T> From<CroQue<T>> for VecDeque<T> {
+    fn from(mut que: CroQue<T>) -> Self {
+        let buffer = std::mem::take(&mut que.buffer);
+        VecDeque::from(Vec::from(buffer))
+    }
+}
@@ -1,61 +1,402 @@
-use std::cmp::Ordering;
-use std::iter::FromIterator;
-use std::ops::{Bound, RangeBounds};
-
-use crate::maps::CroBTree;
+use std::borrow::Borrow;
+use std::cmp::{self, Ordering};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::iter::{FromIterator, Peekable};
+use std::ops::{
+    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Bound, RangeBounds, Sub, SubAssign,
+};
+
+use crate::cmp::{Comparator, CroNaturalOrder};
+use crate::invariants::CroInvariantError;
+use crate::maps::{CroBTree, CroBTreeCursor, CroBTreeIntoIter, CroBTreeIter, CroBTreeRange};
 use crate::sequences::CroVec;
 
-#[derive(Debug)]
-pub struct CroBTreeSet<K> {
-    tree: CroBTree<K, ()>,
+pub struct CroBTreeSet<K, C = CroNaturalOrder> {
+    tree: CroBTree<K, (), 6, (), C>,
 }
 
 pub struct CroBTreeSetIter<'a, K> {
-    items: CroVec<&'a K>,
-    index: usize,
+    iter: CroBTreeIter<'a, K, ()>,
 }
 
 pub struct CroBTreeSetIntoIter<K> {
-    items: CroVec<K>,
-    index: usize,
+    iter: CroBTreeIntoIter<K, ()>,
+}
+
+pub struct CroBTreeSetRange<'a, K> {
+    range: CroBTreeRange<'a, K, ()>,
+}
+
+// The set algebra iterators merge the two sorted key streams lazily.
+pub struct CroBTreeSetUnion<'a, K, C = CroNaturalOrder> {
+    a: Peekable<CroBTreeSetIter<'a, K>>,
+    b: Peekable<CroBTreeSetIter<'a, K>>,
+    comparator: &'a C,
 }
 
-pub type CroBTreeSetRange<'a, K> = CroBTreeSetIter<'a, K>;
-pub type CroBTreeSetUnion<'a, K> = CroBTreeSetIter<'a, K>;
-pub type CroBTreeSetIntersection<'a, K> = CroBTreeSetIter<'a, K>;
-pub type CroBTreeSetDifference<'a, K> = CroBTreeSetIter<'a, K>;
-pub type CroBTreeSetSymmetricDifference<'a, K> = CroBTreeSetIter<'a, K>;
+pub struct CroBTreeSetIntersection<'a, K, C = CroNaturalOrder> {
+    inner: IntersectionInner<'a, K>,
+    comparator: &'a C,
+}
+
+// Sets of similar size are stitched together; when one side is much smaller
+// its keys are looked up by galloping a cursor through the larger one.
+enum IntersectionInner<'a, K> {
+    Stitch {
+        a: CroBTreeSetIter<'a, K>,
+        b: CroBTreeSetIter<'a, K>,
+    },
+    Gallop {
+        small: CroBTreeSetIter<'a, K>,
+        large: CroBTreeCursor<'a, K, ()>,
+    },
+}
+
+pub struct CroBTreeSetDifference<'a, K, C = CroNaturalOrder> {
+    a: CroBTreeSetIter<'a, K>,
+    b: Peekable<CroBTreeSetIter<'a, K>>,
+    comparator: &'a C,
+}
+
+pub struct CroBTreeSetSymmetricDifference<'a, K, C = CroNaturalOrder> {
+    a: Peekable<CroBTreeSetIter<'a, K>>,
+    b: Peekable<CroBTreeSetIter<'a, K>>,
+    comparator: &'a C,
+}
+
+// K-way union: a min-heap holds the next key of every set, tagged with the
+// set it came from.
+pub struct CroBTreeSetUnionAll<'a, K, C = CroNaturalOrder> {
+    iters: CroVec<CroBTreeSetIter<'a, K>>,
+    heap: CroVec<(&'a K, usize)>,
+    comparator: Option<&'a C>,
+}
+
+// K-way intersection as a leapfrog join: each cursor in turn gallops up to
+// the current candidate, and a key is produced once every cursor agrees.
+pub struct CroBTreeSetIntersectAll<'a, K, C = CroNaturalOrder> {
+    cursors: CroVec<CroBTreeCursor<'a, K, ()>>,
+    remaining: usize,
+    comparator: Option<&'a C>,
+}
+
+// Past this size ratio, intersection gallops instead of stitching.
+const GALLOP_RATIO: usize = 16;
 
 impl<'a, K> Iterator for CroBTreeSetIter<'a, K> {
     type Item = &'a K;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.items.size() {
-            let item = self.items[self.index];
-            self.index += 1;
-            Some(&*item)
-        } else {
-            None
-        }
+        self.iter.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K> DoubleEndedIterator for CroBTreeSetIter<'_, K> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(key, _)| key)
     }
 }
 
+impl<K> ExactSizeIterator for CroBTreeSetIter<'_, K> {}
+
 impl<K> Iterator for CroBTreeSetIntoIter<K> {
     type Item = K;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.items.size() {
-            let item = self.items.remove(self.index);
-            Some(item)
-        } else {
-            None
+        self.iter.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K> DoubleEndedIterator for CroBTreeSetIntoIter<K> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<K> ExactSizeIterator for CroBTreeSetIntoIter<K> {}
+
+impl<'a, K> Iterator for CroBTreeSetRange<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.range.next().map(|(key, _)| key)
+    }
+}
+
+impl<K> DoubleEndedIterator for CroBTreeSetRange<'_, K> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.range.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<'a, K, C> Iterator for CroBTreeSetUnion<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ordering = match (self.a.peek(), self.b.peek()) {
+            (Some(a), Some(b)) => self.comparator.compare(a, b),
+            (Some(_), None) => Ordering::Less,
+            (None, _) => Ordering::Greater,
+        };
+        match ordering {
+            Ordering::Less => self.a.next(),
+            Ordering::Greater => self.b.next(),
+            Ordering::Equal => {
+                self.b.next();
+                self.a.next()
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let a = self.a.size_hint().0;
+        let b = self.b.size_hint().0;
+        (cmp::max(a, b), Some(a + b))
+    }
+}
+
+impl<'a, K, C> Iterator for CroBTreeSetIntersection<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let comparator = self.comparator;
+        match &mut self.inner {
+            IntersectionInner::Stitch { a, b } => {
+                let mut a_next = a.next()?;
+                let mut b_next = b.next()?;
+                loop {
+                    match comparator.compare(a_next, b_next) {
+                        Ordering::Less => a_next = a.next()?,
+                        Ordering::Greater => b_next = b.next()?,
+                        Ordering::Equal => return Some(a_next),
+                    }
+                }
+            }
+            IntersectionInner::Gallop { small, large } => loop {
+                let key = small.next()?;
+                large.seek_forward(|stored| comparator.compare(stored, key).is_lt());
+                let (found, _) = large.peek_next()?;
+                if comparator.compare(found, key).is_eq() {
+                    large.next();
+                    return Some(key);
+                }
+            },
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.inner {
+            IntersectionInner::Stitch { a, b } => (0, Some(cmp::min(a.len(), b.len()))),
+            IntersectionInner::Gallop { small, .. } => (0, Some(small.len())),
+        }
+    }
+}
+
+impl<'a, K, C> Iterator for CroBTreeSetDifference<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let key = self.a.next()?;
+            loop {
+                match self.b.peek() {
+                    Some(other) => match self.comparator.compare(key, other) {
+                        Ordering::Less => return Some(key),
+                        Ordering::Equal => {
+                            self.b.next();
+                            break;
+                        }
+                        Ordering::Greater => {
+                            self.b.next();
+                        }
+                    },
+                    None => return Some(key),
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let a = self.a.len();
+        (a.saturating_sub(self.b.size_hint().0), Some(a))
+    }
+}
+
+impl<'a, K, C> Iterator for CroBTreeSetSymmetricDifference<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let ordering = match (self.a.peek(), self.b.peek()) {
+                (Some(a), Some(b)) => self.comparator.compare(a, b),
+                (Some(_), None) => Ordering::Less,
+                (None, _) => Ordering::Greater,
+            };
+            match ordering {
+                Ordering::Less => return self.a.next(),
+                Ordering::Greater => return self.b.next(),
+                Ordering::Equal => {
+                    self.a.next();
+                    self.b.next();
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let a = self.a.size_hint().0;
+        let b = self.b.size_hint().0;
+        (0, Some(a + b))
+    }
+}
+
+impl<'a, K, C> CroBTreeSetUnionAll<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    fn less(&self, a: usize, b: usize) -> bool {
+        let (a_key, a_set) = self.heap[a];
+        let (b_key, b_set) = self.heap[b];
+        match self.comparator.unwrap().compare(a_key, b_key) {
+            Ordering::Equal => a_set < b_set,
+            ordering => ordering.is_lt(),
+        }
+    }
+
+    fn sift_up(&mut self, mut index: usize) {
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            if !self.less(index, parent) {
+                break;
+            }
+            self.heap.swap(index, parent);
+            index = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut index: usize) {
+        loop {
+            let mut smallest = index;
+            for child in [2 * index + 1, 2 * index + 2] {
+                if child < self.heap.len() && self.less(child, smallest) {
+                    smallest = child;
+                }
+            }
+            if smallest == index {
+                break;
+            }
+            self.heap.swap(index, smallest);
+            index = smallest;
+        }
+    }
+
+    // Takes the smallest key off the heap and refills from its set.
+    fn pop(&mut self) -> Option<&'a K> {
+        let (key, set) = *self.heap.first()?;
+        match self.iters[set].next() {
+            Some(next) => self.heap[0] = (next, set),
+            None => {
+                let last = self.heap.len() - 1;
+                self.heap.swap(0, last);
+                self.heap.pop();
+            }
+        }
+        self.sift_down(0);
+        Some(key)
+    }
+}
+
+impl<'a, K, C> Iterator for CroBTreeSetUnionAll<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.pop()?;
+        let comparator = self.comparator.unwrap();
+        while let Some(&(other, _)) = self.heap.first() {
+            if !comparator.compare(other, key).is_eq() {
+                break;
+            }
+            self.pop();
+        }
+        Some(key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Duplicates of the last key are already gone, so everything left in
+        // any one set is still to come.
+        let mut lower = 0;
+        let mut upper = self.heap.len();
+        for iter in self.iters.iter() {
+            upper += iter.len();
+        }
+        for &(_, set) in self.heap.iter() {
+            lower = cmp::max(lower, self.iters[set].len() + 1);
         }
+        (lower, Some(upper))
+    }
+}
+
+impl<'a, K, C> Iterator for CroBTreeSetIntersectAll<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let comparator = self.comparator?;
+        let count = self.cursors.len();
+        let (mut candidate, _) = self.cursors[0].peek_next()?;
+        let mut agreed = 1;
+        let mut index = 1 % count;
+        while agreed < count {
+            let cursor = &mut self.cursors[index];
+            cursor.seek_forward(|stored| comparator.compare(stored, candidate).is_lt());
+            let (key, _) = cursor.peek_next()?;
+            if comparator.compare(key, candidate).is_eq() {
+                agreed += 1;
+            } else {
+                candidate = key;
+                agreed = 1;
+            }
+            index = (index + 1) % count;
+        }
+        self.cursors[0].next();
+        self.remaining -= 1;
+        Some(candidate)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
     }
 }
 
 impl<K> CroBTreeSet<K>
 where
-    K: Ord + Clone,
+    K: Ord,
 {
     pub fn new() -> Self {
         Self {
@@ -68,6 +409,38 @@ where
             tree: CroBTree::with_order(order),
         }
     }
+}
+
+impl<K, C> CroBTreeSet<K, C>
+where
+    C: Comparator<K>,
+{
+    pub fn with_comparator(comparator: C) -> Self {
+        Self {
+            tree: CroBTree::with_comparator(comparator),
+        }
+    }
+
+    pub fn with_order_and_comparator(order: usize, comparator: C) -> Self {
+        Self {
+            tree: CroBTree::with_order_and_comparator(order, comparator),
+        }
+    }
+
+    // Keys must be ascending under `C`.
+    pub fn from_sorted_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        C: Default,
+    {
+        Self {
+            tree: CroBTree::from_sorted_iter(iter.into_iter().map(|key| (key, ()))),
+        }
+    }
+
+    pub fn comparator(&self) -> &C {
+        self.tree.comparator()
+    }
 
     pub fn len(&self) -> usize {
         self.tree.len()
@@ -85,35 +458,40 @@ where
         self.tree.insert(key, ()).is_none()
     }
 
-    pub fn contains(&self, key: &K) -> bool {
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
         self.tree.contains_key(key)
     }
 
-    pub fn get(&self, key: &K) -> Option<&K> {
-        for item in self.iter() {
-            if item == key {
-                return Some(item);
-            }
-        }
-        None
+    pub fn get<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        self.tree.get_key_value(key).map(|(k, _)| k)
     }
 
-    pub fn remove(&mut self, key: &K) -> bool {
+    pub fn remove<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
         self.take(key).is_some()
     }
 
-    pub fn take(&mut self, key: &K) -> Option<K> {
-        let mut removed = None;
-        let mut next = CroBTreeSet::with_order(self.tree.order());
-        for item in self.iter() {
-            if item == key {
-                removed = Some(item.clone());
-            } else {
-                next.insert(item.clone());
-            }
-        }
-        *self = next;
-        removed
+    pub fn take<Q>(&mut self, key: &Q) -> Option<K>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        self.tree.remove_entry(key).map(|(k, _)| k)
     }
 
     pub fn replace(&mut self, key: K) -> Option<K> {
@@ -123,225 +501,185 @@ where
     }
 
     pub fn first(&self) -> Option<&K> {
-        self.iter().next()
+        self.tree.first_key_value().map(|(key, _)| key)
     }
 
     pub fn last(&self) -> Option<&K> {
-        let mut iter = self.iter();
-        iter.next_back()
+        self.tree.last_key_value().map(|(key, _)| key)
+    }
+
+    pub fn nth(&self, index: usize) -> Option<&K> {
+        self.tree.get_index(index).map(|(key, _)| key)
+    }
+
+    pub fn position<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        if self.tree.contains_key(key) {
+            Some(self.tree.rank(key))
+        } else {
+            None
+        }
     }
 
     pub fn pop_first(&mut self) -> Option<K> {
-        let key = self.first()?.clone();
-        self.remove(&key);
-        Some(key)
+        self.tree.pop_first().map(|(key, _)| key)
     }
 
     pub fn pop_last(&mut self) -> Option<K> {
-        let key = self.last()?.clone();
-        self.remove(&key);
-        Some(key)
+        self.tree.pop_last().map(|(key, _)| key)
     }
 
     pub fn iter(&self) -> CroBTreeSetIter<'_, K> {
-        let mut items = CroVec::new();
-        for (key, _) in self.tree.iter() {
-            items.push(key);
-        }
-        CroBTreeSetIter { items, index: 0 }
+        CroBTreeSetIter { iter: self.tree.iter() }
     }
 
-    pub fn range<R>(&self, range: R) -> CroBTreeSetRange<'_, K>
+    pub fn range<T, R>(&self, range: R) -> CroBTreeSetRange<'_, K>
     where
-        R: RangeBounds<K>,
+        K: Borrow<T>,
+        T: ?Sized,
+        C: Comparator<T>,
+        R: RangeBounds<T>,
     {
-        let mut items = CroVec::new();
-        for key in self.iter() {
-            if in_range(&range, key) {
-                items.push(key);
-            }
+        CroBTreeSetRange {
+            range: self.tree.range(range),
         }
-        CroBTreeSetIter { items, index: 0 }
     }
 
     pub fn retain<F>(&mut self, mut f: F)
     where
         F: FnMut(&K) -> bool,
     {
-        let mut next = CroBTreeSet::with_order(self.tree.order());
-        for item in self.iter() {
-            if f(item) {
-                next.insert(item.clone());
-            }
-        }
-        *self = next;
+        self.tree.retain(|key, _| f(key));
     }
 
-    pub fn append(&mut self, other: &mut CroBTreeSet<K>) {
-        for item in other.iter() {
-            self.insert(item.clone());
-        }
-        other.wipe();
+    pub fn validate(&self) -> Result<(), CroInvariantError> {
+        self.tree.validate()
     }
 
-    pub fn split_off(&mut self, key: &K) -> CroBTreeSet<K> {
-        let mut left = CroBTreeSet::with_order(self.tree.order());
-        let mut right = CroBTreeSet::with_order(self.tree.order());
-        for item in self.iter() {
-            if item < key {
-                left.insert(item.clone());
-            } else {
-                right.insert(item.clone());
-            }
-        }
-        *self = left;
-        right
-    }
-
-    pub fn union<'a>(&'a self, other: &'a CroBTreeSet<K>) -> CroBTreeSetUnion<'a, K> {
-        let left = self.collect_keys();
-        let right = other.collect_keys();
-        let mut items = CroVec::new();
-        let mut i = 0;
-        let mut j = 0;
-        while i < left.size() && j < right.size() {
-            match left[i].cmp(right[j]) {
-                Ordering::Less => {
-                    items.push(left[i]);
-                    i += 1;
-                }
-                Ordering::Greater => {
-                    items.push(right[j]);
-                    j += 1;
-                }
-                Ordering::Equal => {
-                    items.push(left[i]);
-                    i += 1;
-                    j += 1;
-                }
-            }
-        }
-        while i < left.size() {
-            items.push(left[i]);
-            i += 1;
+    pub fn append(&mut self, other: &mut CroBTreeSet<K, C>) {
+        self.tree.append(&mut other.tree);
+    }
+
+    pub fn split_off<Q>(&mut self, key: &Q) -> CroBTreeSet<K, C>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        CroBTreeSet {
+            tree: self.tree.split_off(key),
         }
-        while j < right.size() {
-            items.push(right[j]);
-            j += 1;
+    }
+
+    pub fn union<'a>(&'a self, other: &'a CroBTreeSet<K, C>) -> CroBTreeSetUnion<'a, K, C> {
+        CroBTreeSetUnion {
+            a: self.iter().peekable(),
+            b: other.iter().peekable(),
+            comparator: self.comparator(),
         }
-        CroBTreeSetIter { items, index: 0 }
     }
 
     pub fn intersection<'a>(
         &'a self,
-        other: &'a CroBTreeSet<K>,
-    ) -> CroBTreeSetIntersection<'a, K> {
-        let left = self.collect_keys();
-        let right = other.collect_keys();
-        let mut items = CroVec::new();
-        let mut i = 0;
-        let mut j = 0;
-        while i < left.size() && j < right.size() {
-            match left[i].cmp(right[j]) {
-                Ordering::Less => i += 1,
-                Ordering::Greater => j += 1,
-                Ordering::Equal => {
-                    items.push(left[i]);
-                    i += 1;
-                    j += 1;
-                }
+        other: &'a CroBTreeSet<K, C>,
+    ) -> CroBTreeSetIntersection<'a, K, C> {
+        let (small, large) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let inner = if small.len() * GALLOP_RATIO < large.len() {
+            IntersectionInner::Gallop {
+                small: small.iter(),
+                large: large.tree.lower_bound::<K>(Bound::Unbounded),
+            }
+        } else {
+            IntersectionInner::Stitch {
+                a: self.iter(),
+                b: other.iter(),
             }
+        };
+        CroBTreeSetIntersection {
+            inner,
+            comparator: self.comparator(),
         }
-        CroBTreeSetIter { items, index: 0 }
     }
 
-    pub fn difference<'a>(&'a self, other: &'a CroBTreeSet<K>) -> CroBTreeSetDifference<'a, K> {
-        let left = self.collect_keys();
-        let right = other.collect_keys();
-        let mut items = CroVec::new();
-        let mut i = 0;
-        let mut j = 0;
-        while i < left.size() {
-            if j >= right.size() {
-                items.push(left[i]);
-                i += 1;
-                continue;
-            }
-            match left[i].cmp(right[j]) {
-                Ordering::Less => {
-                    items.push(left[i]);
-                    i += 1;
-                }
-                Ordering::Greater => {
-                    j += 1;
-                }
-                Ordering::Equal => {
-                    i += 1;
-                    j += 1;
-                }
-            }
+    pub fn difference<'a>(&'a self, other: &'a CroBTreeSet<K, C>) -> CroBTreeSetDifference<'a, K, C> {
+        CroBTreeSetDifference {
+            a: self.iter(),
+            b: other.iter().peekable(),
+            comparator: self.comparator(),
         }
-        CroBTreeSetIter { items, index: 0 }
     }
 
     pub fn symmetric_difference<'a>(
         &'a self,
-        other: &'a CroBTreeSet<K>,
-    ) -> CroBTreeSetSymmetricDifference<'a, K> {
-        let left = self.collect_keys();
-        let right = other.collect_keys();
-        let mut items = CroVec::new();
-        let mut i = 0;
-        let mut j = 0;
-        while i < left.size() || j < right.size() {
-            if i >= left.size() {
-                items.push(right[j]);
-                j += 1;
-                continue;
-            }
-            if j >= right.size() {
-                items.push(left[i]);
-                i += 1;
-                continue;
-            }
-            match left[i].cmp(right[j]) {
-                Ordering::Less => {
-                    items.push(left[i]);
-                    i += 1;
-                }
-                Ordering::Greater => {
-                    items.push(right[j]);
-                    j += 1;
-                }
-                Ordering::Equal => {
-                    i += 1;
-                    j += 1;
-                }
-            }
+        other: &'a CroBTreeSet<K, C>,
+    ) -> CroBTreeSetSymmetricDifference<'a, K, C> {
+        CroBTreeSetSymmetricDifference {
+            a: self.iter().peekable(),
+            b: other.iter().peekable(),
+            comparator: self.comparator(),
         }
-        CroBTreeSetIter { items, index: 0 }
     }
 
-    pub fn is_subset(&self, other: &CroBTreeSet<K>) -> bool {
-        for item in self.iter() {
-            if !other.contains(item) {
-                return false;
+    // Sets are expected to share an ordering; the first set's comparator is
+    // used for all of them.
+    pub fn union_all<'a>(sets: &[&'a CroBTreeSet<K, C>]) -> CroBTreeSetUnionAll<'a, K, C> {
+        let mut union = CroBTreeSetUnionAll {
+            iters: CroVec::with_cap(sets.len()),
+            heap: CroVec::with_cap(sets.len()),
+            comparator: sets.first().map(|set| set.comparator()),
+        };
+        for set in sets {
+            let mut iter = set.iter();
+            if let Some(key) = iter.next() {
+                union.heap.push((key, union.iters.len()));
+                let last = union.heap.len() - 1;
+                union.sift_up(last);
             }
+            union.iters.push(iter);
         }
-        true
+        union
     }
 
-    pub fn is_superset(&self, other: &CroBTreeSet<K>) -> bool {
-        other.is_subset(self)
+    // The smallest set drives the join, so the work is bounded by its size
+    // times the cost of galloping through the others.
+    pub fn intersect_all<'a>(sets: &[&'a CroBTreeSet<K, C>]) -> CroBTreeSetIntersectAll<'a, K, C> {
+        let mut sets: CroVec<&'a CroBTreeSet<K, C>> = sets.iter().copied().collect();
+        sets.as_mut_slice().sort_by_key(|set| set.len());
+        let mut cursors = CroVec::with_cap(sets.len());
+        for set in sets.iter() {
+            cursors.push(set.tree.lower_bound::<K>(Bound::Unbounded));
+        }
+        CroBTreeSetIntersectAll {
+            cursors,
+            remaining: sets.first().map_or(0, |set| set.len()),
+            comparator: sets.first().map(|set| set.comparator()),
+        }
     }
 
-    pub fn is_disjoint(&self, other: &CroBTreeSet<K>) -> bool {
-        for item in self.iter() {
-            if other.contains(item) {
-                return false;
-            }
+    pub fn is_subset(&self, other: &CroBTreeSet<K, C>) -> bool {
+        if self.len() > other.len() {
+            return false;
+        }
+        if self.len() * GALLOP_RATIO < other.len() {
+            return self.iter().all(|key| other.contains(key));
         }
-        true
+        self.difference(other).next().is_none()
+    }
+
+    pub fn is_superset(&self, other: &CroBTreeSet<K, C>) -> bool {
+        other.is_subset(self)
+    }
+
+    pub fn is_disjoint(&self, other: &CroBTreeSet<K, C>) -> bool {
+        self.intersection(other).next().is_none()
     }
 
     pub fn extend<I>(&mut self, iter: I)
@@ -353,30 +691,47 @@ where
         }
     }
 
-    fn collect_keys(&self) -> CroVec<&K> {
-        let mut items = CroVec::new();
-        for item in self.iter() {
-            items.push(item);
-        }
-        items
+    // Builds a set with this one's order and comparator from keys already
+    // ascending under it, without a search per key.
+    fn sorted_like<I>(&self, keys: I) -> Self
+    where
+        I: Iterator<Item = K>,
+    {
+        let order = self.tree.order();
+        let mut set = CroBTreeSet::with_order_and_comparator(order, self.comparator().clone());
+        set.tree.bulk_load(keys.map(|key| (key, ())), order - 1);
+        set
     }
 }
 
-impl<K> Default for CroBTreeSet<K>
+impl<K, C> fmt::Debug for CroBTreeSet<K, C>
 where
-    K: Ord + Clone,
+    K: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CroBTreeSet").field("tree", &self.tree).finish()
+    }
+}
+
+impl<K, C> Default for CroBTreeSet<K, C>
+where
+    C: Comparator<K> + Default,
 {
     fn default() -> Self {
-        Self::new()
+        Self {
+            tree: CroBTree::default(),
+        }
     }
 }
 
-impl<K> Clone for CroBTreeSet<K>
+impl<K, C> Clone for CroBTreeSet<K, C>
 where
-    K: Ord + Clone,
+    K: Clone,
+    C: Comparator<K>,
 {
     fn clone(&self) -> Self {
-        let mut next = CroBTreeSet::with_order(self.tree.order());
+        let comparator = self.comparator().clone();
+        let mut next = CroBTreeSet::with_order_and_comparator(self.tree.order(), comparator);
         for key in self.iter() {
             next.insert(key.clone());
         }
@@ -384,9 +739,9 @@ where
     }
 }
 
-impl<K> PartialEq for CroBTreeSet<K>
+impl<K, C> PartialEq for CroBTreeSet<K, C>
 where
-    K: Ord + Clone,
+    C: Comparator<K>,
 {
     fn eq(&self, other: &Self) -> bool {
         if self.len() != other.len() {
@@ -397,27 +752,27 @@ where
         loop {
             match (left.next(), right.next()) {
                 (None, None) => return true,
-                (Some(a), Some(b)) if a == b => continue,
+                (Some(a), Some(b)) if self.comparator().compare(a, b).is_eq() => continue,
                 _ => return false,
             }
         }
     }
 }
 
-impl<K> Eq for CroBTreeSet<K> where K: Ord + Clone {}
+impl<K, C> Eq for CroBTreeSet<K, C> where C: Comparator<K> {}
 
-impl<K> PartialOrd for CroBTreeSet<K>
+impl<K, C> PartialOrd for CroBTreeSet<K, C>
 where
-    K: Ord + Clone,
+    C: Comparator<K>,
 {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl<K> Ord for CroBTreeSet<K>
+impl<K, C> Ord for CroBTreeSet<K, C>
 where
-    K: Ord + Clone,
+    C: Comparator<K>,
 {
     fn cmp(&self, other: &Self) -> Ordering {
         let mut left = self.iter();
@@ -427,7 +782,7 @@ where
                 (None, None) => return Ordering::Equal,
                 (None, Some(_)) => return Ordering::Less,
                 (Some(_), None) => return Ordering::Greater,
-                (Some(a), Some(b)) => match a.cmp(b) {
+                (Some(a), Some(b)) => match self.comparator().compare(a, b) {
                     Ordering::Equal => continue,
                     ordering => return ordering,
                 },
@@ -436,29 +791,29 @@ where
     }
 }
 
-impl<K> FromIterator<K> for CroBTreeSet<K>
+impl<K, C> FromIterator<K> for CroBTreeSet<K, C>
 where
-    K: Ord + Clone,
+    C: Comparator<K> + Default,
 {
     fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
-        let mut set = CroBTreeSet::new();
+        let mut set = CroBTreeSet::default();
         set.extend(iter);
         set
     }
 }
 
-impl<K> Extend<K> for CroBTreeSet<K>
+impl<K, C> Extend<K> for CroBTreeSet<K, C>
 where
-    K: Ord + Clone,
+    C: Comparator<K>,
 {
     fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
         CroBTreeSet::extend(self, iter);
     }
 }
 
-impl<'a, K> IntoIterator for &'a CroBTreeSet<K>
+impl<'a, K, C> IntoIterator for &'a CroBTreeSet<K, C>
 where
-    K: Ord + Clone,
+    C: Comparator<K>,
 {
     type Item = &'a K;
     type IntoIter = CroBTreeSetIter<'a, K>;
@@ -468,51 +823,126 @@ where
     }
 }
 
-impl<K> IntoIterator for CroBTreeSet<K>
+impl<K, C> IntoIterator for CroBTreeSet<K, C>
 where
-    K: Ord + Clone,
+    C: Comparator<K>,
 {
     type Item = K;
     type IntoIter = CroBTreeSetIntoIter<K>;
 
     fn into_iter(self) -> Self::IntoIter {
-        let mut items = CroVec::new();
-        for key in self.iter() {
-            items.push(key.clone());
+        CroBTreeSetIntoIter {
+            iter: self.tree.into_iter(),
         }
-        CroBTreeSetIntoIter { items, index: 0 }
     }
 }
 
-impl<'a, K> CroBTreeSetIter<'a, K> {
-    fn next_back(&mut self) -> Option<&'a K> {
-        if self.items.size() == 0 || self.index >= self.items.size() {
-            return None;
+impl<K, C> BitOr<&CroBTreeSet<K, C>> for &CroBTreeSet<K, C>
+where
+    K: Clone,
+    C: Comparator<K>,
+{
+    type Output = CroBTreeSet<K, C>;
+
+    fn bitor(self, rhs: &CroBTreeSet<K, C>) -> CroBTreeSet<K, C> {
+        self.sorted_like(self.union(rhs).cloned())
+    }
+}
+
+impl<K, C> BitAnd<&CroBTreeSet<K, C>> for &CroBTreeSet<K, C>
+where
+    K: Clone,
+    C: Comparator<K>,
+{
+    type Output = CroBTreeSet<K, C>;
+
+    fn bitand(self, rhs: &CroBTreeSet<K, C>) -> CroBTreeSet<K, C> {
+        self.sorted_like(self.intersection(rhs).cloned())
+    }
+}
+
+impl<K, C> Sub<&CroBTreeSet<K, C>> for &CroBTreeSet<K, C>
+where
+    K: Clone,
+    C: Comparator<K>,
+{
+    type Output = CroBTreeSet<K, C>;
+
+    fn sub(self, rhs: &CroBTreeSet<K, C>) -> CroBTreeSet<K, C> {
+        self.sorted_like(self.difference(rhs).cloned())
+    }
+}
+
+impl<K, C> BitXor<&CroBTreeSet<K, C>> for &CroBTreeSet<K, C>
+where
+    K: Clone,
+    C: Comparator<K>,
+{
+    type Output = CroBTreeSet<K, C>;
+
+    fn bitxor(self, rhs: &CroBTreeSet<K, C>) -> CroBTreeSet<K, C> {
+        self.sorted_like(self.symmetric_difference(rhs).cloned())
+    }
+}
+
+impl<K, C> BitOrAssign<&CroBTreeSet<K, C>> for CroBTreeSet<K, C>
+where
+    K: Clone,
+    C: Comparator<K>,
+{
+    fn bitor_assign(&mut self, rhs: &CroBTreeSet<K, C>) {
+        for key in rhs.iter() {
+            if !self.contains(key) {
+                self.insert(key.clone());
+            }
         }
-        let last_index = self.items.size() - 1;
-        if self.index > last_index {
-            return None;
+    }
+}
+
+impl<K, C> BitAndAssign<&CroBTreeSet<K, C>> for CroBTreeSet<K, C>
+where
+    C: Comparator<K>,
+{
+    fn bitand_assign(&mut self, rhs: &CroBTreeSet<K, C>) {
+        self.retain(|key| rhs.contains(key));
+    }
+}
+
+impl<K, C> SubAssign<&CroBTreeSet<K, C>> for CroBTreeSet<K, C>
+where
+    C: Comparator<K>,
+{
+    fn sub_assign(&mut self, rhs: &CroBTreeSet<K, C>) {
+        self.retain(|key| !rhs.contains(key));
+    }
+}
+
+impl<K, C> BitXorAssign<&CroBTreeSet<K, C>> for CroBTreeSet<K, C>
+where
+    K: Clone,
+    C: Comparator<K>,
+{
+    fn bitxor_assign(&mut self, rhs: &CroBTreeSet<K, C>) {
+        for key in rhs.iter() {
+            if !self.remove(key) {
+                self.insert(key.clone());
+            }
         }
-        let item = self.items[last_index];
-        self.items.remove(last_index);
-        Some(&*item)
     }
 }
 
-fn in_range<K, R>(range: &R, key: &K) -> bool
+// A `BTreeSet` iterates in ascending order, so it bulk loads.
+impl<K: Ord> From<BTreeSet<K>> for CroBTreeSet<K> {
+    fn from(set: BTreeSet<K>) -> Self {
+        CroBTreeSet::from_sorted_iter(set)
+    }
+}
+
+impl<K: Ord, C> From<CroBTreeSet<K, C>> for BTreeSet<K>
 where
-    K: Ord,
-    R: RangeBounds<K>,
+    C: Comparator<K>,
 {
-    let lower_ok = match range.start_bound() {
-        Bound::Included(start) => key >= start,
-        Bound::Excluded(start) => key > start,
-        Bound::Unbounded => true,
-    };
-    let upper_ok = match range.end_bound() {
-        Bound::Included(end) => key <= end,
-        Bound::Excluded(end) => key < end,
-        Bound::Unbounded => true,
-    };
-    lower_ok && upper_ok
+    fn from(set: CroBTreeSet<K, C>) -> Self {
+        set.into_iter().collect()
+    }
 }
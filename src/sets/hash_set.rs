@@ -1,9 +1,13 @@
 use std::borrow::Borrow;
+use std::cmp::Reverse;
 use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
 use std::hash::{BuildHasher, Hash};
-use std::iter::FromIterator;
+use std::iter::{Chain, FromIterator};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Sub, SubAssign};
 
-use crate::maps::{CroMap, CroMapDrain, CroMapIntoIter};
+use crate::invariants::CroInvariantError;
+use crate::maps::{CroMap, CroMapDrain, CroMapIntoIter, CroMapIter};
 use crate::sequences::CroVec;
 
 #[derive(Debug)]
@@ -11,9 +15,8 @@ pub struct CroHashSet<K, S = RandomState> {
     map: CroMap<K, (), S>,
 }
 
-pub struct CroHashSetIter<'a, K> {
-    items: CroVec<&'a K>,
-    index: usize,
+pub struct CroHashSetIter<'a, K, S = RandomState> {
+    iter: CroMapIter<'a, K, (), S>,
 }
 
 pub struct CroHashSetIntoIter<K, S> {
@@ -24,23 +27,173 @@ pub struct CroHashSetDrain<K> {
     iter: CroMapDrain<K, ()>,
 }
 
-pub type CroHashSetUnion<'a, K> = CroHashSetIter<'a, K>;
-pub type CroHashSetIntersection<'a, K> = CroHashSetIter<'a, K>;
-pub type CroHashSetDifference<'a, K> = CroHashSetIter<'a, K>;
-pub type CroHashSetSymmetricDifference<'a, K> = CroHashSetIter<'a, K>;
+pub struct CroHashSetUnion<'a, K, S = RandomState> {
+    iter: Chain<CroHashSetIter<'a, K, S>, CroHashSetDifference<'a, K, S>>,
+}
+
+// Walks the smaller of the two sets and probes the larger one.
+pub struct CroHashSetIntersection<'a, K, S = RandomState> {
+    iter: CroHashSetIter<'a, K, S>,
+    other: &'a CroHashSet<K, S>,
+}
+
+pub struct CroHashSetDifference<'a, K, S = RandomState> {
+    iter: CroHashSetIter<'a, K, S>,
+    other: &'a CroHashSet<K, S>,
+}
+
+pub struct CroHashSetSymmetricDifference<'a, K, S = RandomState> {
+    iter: Chain<CroHashSetDifference<'a, K, S>, CroHashSetDifference<'a, K, S>>,
+}
+
+// Keys of each set in turn, largest set first. Later keys are checked
+// against the largest set and against `seen`, which holds the keys yielded
+// from the smaller sets, so each key costs O(1) however many sets there are.
+pub struct CroHashSetUnionAll<'a, K, S = RandomState> {
+    sets: CroVec<&'a CroHashSet<K, S>>,
+    index: usize,
+    iter: Option<CroHashSetIter<'a, K, S>>,
+    seen: CroHashSet<&'a K>,
+}
 
-impl<'a, K> Iterator for CroHashSetIter<'a, K> {
+// Walks the smallest set and probes the rest, smallest first so that misses
+// are found early.
+pub struct CroHashSetIntersectAll<'a, K, S = RandomState> {
+    iter: Option<CroHashSetIter<'a, K, S>>,
+    others: CroVec<&'a CroHashSet<K, S>>,
+}
+
+impl<'a, K, S> Iterator for CroHashSetIter<'a, K, S> {
     type Item = &'a K;
-    
+
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.items.size() {
-            let item = self.items[self.index];
-            self.index += 1;
-            Some(&*item)
-        } else {
-            None
+        self.iter.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K, S> ExactSizeIterator for CroHashSetIter<'_, K, S> {}
+
+impl<'a, K, S> Iterator for CroHashSetUnion<'a, K, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, S> Iterator for CroHashSetIntersection<'a, K, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let other = self.other;
+        self.iter.find(|key| other.contains(*key))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.iter.len()))
+    }
+}
+
+impl<'a, K, S> Iterator for CroHashSetDifference<'a, K, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let other = self.other;
+        self.iter.find(|key| !other.contains(*key))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.iter.len();
+        (remaining.saturating_sub(self.other.len()), Some(remaining))
+    }
+}
+
+impl<'a, K, S> Iterator for CroHashSetSymmetricDifference<'a, K, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, S> Iterator for CroHashSetUnionAll<'a, K, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.iter.as_mut()?.next() {
+                Some(key) => {
+                    if self.index == 0 || (!self.sets[0].contains(key) && self.seen.insert(key)) {
+                        return Some(key);
+                    }
+                }
+                None => {
+                    self.index += 1;
+                    self.iter = self.sets.get(self.index).map(|set| set.iter());
+                }
+            }
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let current = self.iter.as_ref().map_or(0, |iter| iter.len());
+        let mut upper = current;
+        for set in self.sets.iter().skip(self.index + 1) {
+            upper += set.len();
+        }
+        // Only the first set is known to contribute all of its keys.
+        let lower = if self.index == 0 { current } else { 0 };
+        (lower, Some(upper))
+    }
+}
+
+impl<'a, K, S> Iterator for CroHashSetIntersectAll<'a, K, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let others = &self.others;
+        self.iter.as_mut()?.find(|key| others.iter().all(|set| set.contains(*key)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.iter.as_ref().map_or(0, |iter| iter.len())))
+    }
 }
 
 impl<K, S> Iterator for CroHashSetIntoIter<K, S> {
@@ -49,6 +202,10 @@ impl<K, S> Iterator for CroHashSetIntoIter<K, S> {
     fn next(&mut self) -> Option<Self::Item> {
         self.iter.next().map(|(key, _)| key)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
 }
 
 impl<K> Iterator for CroHashSetDrain<K> {
@@ -57,8 +214,16 @@ impl<K> Iterator for CroHashSetDrain<K> {
     fn next(&mut self) -> Option<Self::Item> {
         self.iter.next().map(|(key, _)| key)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
 }
 
+impl<K, S> ExactSizeIterator for CroHashSetIntoIter<K, S> {}
+
+impl<K> ExactSizeIterator for CroHashSetDrain<K> {}
+
 impl<K> CroHashSet<K>
 where
     K: Eq + Hash,
@@ -110,6 +275,10 @@ where
         self.map.is_empty()
     }
 
+    pub fn validate(&self) -> Result<(), CroInvariantError> {
+        self.map.validate()
+    }
+
     pub fn wipe(&mut self) {
         self.map.wipe();
     }
@@ -156,12 +325,8 @@ where
         old
     }
 
-    pub fn iter(&self) -> CroHashSetIter<'_, K> {
-        let mut items = CroVec::new();
-        for (key, _) in self.map.iter() {
-            items.push(key);
-        }
-        CroHashSetIter { items, index: 0 }
+    pub fn iter(&self) -> CroHashSetIter<'_, K, S> {
+        CroHashSetIter { iter: self.map.iter() }
     }
 
     pub fn drain(&mut self) -> CroHashSetDrain<K> {
@@ -174,11 +339,7 @@ where
     where
         F: FnMut(&K) -> bool,
     {
-        for (key, _) in self.map.drain() {
-            if f(&key) {
-                self.map.insert(key, ());
-            }
-        }
+        self.map.retain(|key, _| f(key));
     }
 
     pub fn append(&mut self, other: &mut CroHashSet<K, S>) {
@@ -187,70 +348,66 @@ where
         }
     }
 
-    pub fn union<'a>(&'a self, other: &'a CroHashSet<K, S>) -> CroHashSetUnion<'a, K> {
-        let mut items = CroVec::new();
-        for key in self.iter() {
-            items.push(key);
+    pub fn union<'a>(&'a self, other: &'a CroHashSet<K, S>) -> CroHashSetUnion<'a, K, S> {
+        CroHashSetUnion {
+            iter: self.iter().chain(other.difference(self)),
         }
-        for key in other.iter() {
-            if !self.contains(key) {
-                items.push(key);
-            }
-        }
-        CroHashSetIter { items, index: 0 }
     }
 
     pub fn intersection<'a>(
         &'a self,
         other: &'a CroHashSet<K, S>,
-    ) -> CroHashSetIntersection<'a, K> {
-        let mut items = CroVec::new();
-        for key in self.iter() {
-            if other.contains(key) {
-                items.push(key);
-            }
+    ) -> CroHashSetIntersection<'a, K, S> {
+        let (small, large) = if self.len() <= other.len() { (self, other) } else { (other, self) };
+        CroHashSetIntersection {
+            iter: small.iter(),
+            other: large,
         }
-        CroHashSetIter { items, index: 0 }
     }
 
     pub fn difference<'a>(
         &'a self,
         other: &'a CroHashSet<K, S>,
-    ) -> CroHashSetDifference<'a, K> {
-        let mut items = CroVec::new();
-        for key in self.iter() {
-            if !other.contains(key) {
-                items.push(key);
-            }
+    ) -> CroHashSetDifference<'a, K, S> {
+        CroHashSetDifference {
+            iter: self.iter(),
+            other,
         }
-        CroHashSetIter { items, index: 0 }
     }
 
     pub fn symmetric_difference<'a>(
         &'a self,
         other: &'a CroHashSet<K, S>,
-    ) -> CroHashSetSymmetricDifference<'a, K> {
-        let mut items = CroVec::new();
-        for key in self.iter() {
-            if !other.contains(key) {
-                items.push(key);
-            }
+    ) -> CroHashSetSymmetricDifference<'a, K, S> {
+        CroHashSetSymmetricDifference {
+            iter: self.difference(other).chain(other.difference(self)),
         }
-        for key in other.iter() {
-            if !self.contains(key) {
-                items.push(key);
-            }
+    }
+
+    pub fn union_all<'a>(sets: &[&'a CroHashSet<K, S>]) -> CroHashSetUnionAll<'a, K, S> {
+        let mut sets: CroVec<&'a CroHashSet<K, S>> = sets.iter().copied().collect();
+        sets.as_mut_slice().sort_by_key(|set| Reverse(set.len()));
+        CroHashSetUnionAll {
+            iter: sets.first().map(|set| set.iter()),
+            index: 0,
+            sets,
+            seen: CroHashSet::new(),
         }
-        CroHashSetIter { items, index: 0 }
+    }
+
+    pub fn intersect_all<'a>(sets: &[&'a CroHashSet<K, S>]) -> CroHashSetIntersectAll<'a, K, S> {
+        let mut others: CroVec<&'a CroHashSet<K, S>> = sets.iter().copied().collect();
+        others.as_mut_slice().sort_by_key(|set| set.len());
+        let iter = if others.is_empty() {
+            None
+        } else {
+            Some(others.remove(0).iter())
+        };
+        CroHashSetIntersectAll { iter, others }
     }
 
     pub fn is_subset(&self, other: &CroHashSet<K, S>) -> bool {
-        for key in self.iter() {
-            if !other.contains(key) {
-                return false;
-            }
-        }
-        true
+        self.len() <= other.len() && self.iter().all(|key| other.contains(key))
     }
 
     pub fn is_superset(&self, other: &CroHashSet<K, S>) -> bool {
@@ -258,12 +415,7 @@ where
     }
 
     pub fn is_disjoint(&self, other: &CroHashSet<K, S>) -> bool {
-        for key in self.iter() {
-            if other.contains(key) {
-                return false;
-            }
-        }
-        true
+        self.intersection(other).next().is_none()
     }
 
     pub fn extend<I>(&mut self, iter: I)
@@ -356,7 +508,7 @@ where
     S: BuildHasher,
 {
     type Item = &'a K;
-    type IntoIter = CroHashSetIter<'a, K>;
+    type IntoIter = CroHashSetIter<'a, K, S>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.iter()
@@ -375,3 +527,135 @@ where
         CroHashSetIntoIter { iter: self.map.into_iter() }
     }
 }
+
+impl<K, S> BitOr<&CroHashSet<K, S>> for &CroHashSet<K, S>
+where
+    K: Eq + Hash + Clone,
+    S: BuildHasher + Clone,
+{
+    type Output = CroHashSet<K, S>;
+
+    fn bitor(self, rhs: &CroHashSet<K, S>) -> CroHashSet<K, S> {
+        let mut set = CroHashSet::with_hasher(self.hasher().clone());
+        set.extend(self.union(rhs).cloned());
+        set
+    }
+}
+
+impl<K, S> BitAnd<&CroHashSet<K, S>> for &CroHashSet<K, S>
+where
+    K: Eq + Hash + Clone,
+    S: BuildHasher + Clone,
+{
+    type Output = CroHashSet<K, S>;
+
+    fn bitand(self, rhs: &CroHashSet<K, S>) -> CroHashSet<K, S> {
+        let mut set = CroHashSet::with_hasher(self.hasher().clone());
+        set.extend(self.intersection(rhs).cloned());
+        set
+    }
+}
+
+impl<K, S> Sub<&CroHashSet<K, S>> for &CroHashSet<K, S>
+where
+    K: Eq + Hash + Clone,
+    S: BuildHasher + Clone,
+{
+    type Output = CroHashSet<K, S>;
+
+    fn sub(self, rhs: &CroHashSet<K, S>) -> CroHashSet<K, S> {
+        let mut set = CroHashSet::with_hasher(self.hasher().clone());
+        set.extend(self.difference(rhs).cloned());
+        set
+    }
+}
+
+impl<K, S> BitXor<&CroHashSet<K, S>> for &CroHashSet<K, S>
+where
+    K: Eq + Hash + Clone,
+    S: BuildHasher + Clone,
+{
+    type Output = CroHashSet<K, S>;
+
+    fn bitxor(self, rhs: &CroHashSet<K, S>) -> CroHashSet<K, S> {
+        let mut set = CroHashSet::with_hasher(self.hasher().clone());
+        set.extend(self.symmetric_difference(rhs).cloned());
+        set
+    }
+}
+
+impl<K, S> BitOrAssign<&CroHashSet<K, S>> for CroHashSet<K, S>
+where
+    K: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    fn bitor_assign(&mut self, rhs: &CroHashSet<K, S>) {
+        for key in rhs.iter() {
+            if !self.contains(key) {
+                self.insert(key.clone());
+            }
+        }
+    }
+}
+
+impl<K, S> BitAndAssign<&CroHashSet<K, S>> for CroHashSet<K, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    fn bitand_assign(&mut self, rhs: &CroHashSet<K, S>) {
+        self.retain(|key| rhs.contains(key));
+    }
+}
+
+impl<K, S> SubAssign<&CroHashSet<K, S>> for CroHashSet<K, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    fn sub_assign(&mut self, rhs: &CroHashSet<K, S>) {
+        self.retain(|key| !rhs.contains(key));
+    }
+}
+
+impl<K, S> BitXorAssign<&CroHashSet<K, S>> for CroHashSet<K, S>
+where
+    K: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    fn bitxor_assign(&mut self, rhs: &CroHashSet<K, S>) {
+        for key in rhs.iter() {
+            if !self.remove(key) {
+                self.insert(key.clone());
+            }
+        }
+    }
+}
+
+impl<K, S> From<HashSet<K, S>> for CroHashSet<K, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Clone,
+{
+    fn from(set: HashSet<K, S>) -> Self {
+        let mut out = CroHashSet::with_cap_and_hasher(0, set.hasher().clone());
+        out.reserve(set.len());
+        for key in set {
+            out.insert(key);
+        }
+        out
+    }
+}
+
+impl<K, S> From<CroHashSet<K, S>> for HashSet<K, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn from(set: CroHashSet<K, S>) -> Self {
+        let (entries, build_hasher) = set.map.into_parts();
+        let mut out = HashSet::with_capacity_and_hasher(entries.len(), build_hasher);
+        out.extend(entries.map(|(key, _)| key));
+        out
+    }
+}
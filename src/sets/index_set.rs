@@ -0,0 +1,349 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
+
+use crate::invariants::CroInvariantError;
+use crate::maps::{CroIndexMap, CroIndexMapEntry, CroIndexMapIntoIter, CroIndexMapIter};
+
+pub struct CroIndexSet<K, S = RandomState> {
+    map: CroIndexMap<K, (), S>,
+}
+
+pub struct CroIndexSetIter<'a, K> {
+    iter: CroIndexMapIter<'a, K, ()>,
+}
+
+pub struct CroIndexSetIntoIter<K> {
+    iter: CroIndexMapIntoIter<K, ()>,
+}
+
+impl<'a, K> Iterator for CroIndexSetIter<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K> DoubleEndedIterator for CroIndexSetIter<'_, K> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<K> Iterator for CroIndexSetIntoIter<K> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K> ExactSizeIterator for CroIndexSetIter<'_, K> {}
+
+impl<K> ExactSizeIterator for CroIndexSetIntoIter<K> {}
+
+impl<K> CroIndexSet<K>
+where
+    K: Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self {
+            map: CroIndexMap::new(),
+        }
+    }
+}
+
+impl<K, S> CroIndexSet<K, S> {
+    pub fn len(&self) -> usize {
+        self.map.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn cap(&self) -> usize {
+        self.map.cap()
+    }
+
+    pub fn hasher(&self) -> &S {
+        self.map.hasher()
+    }
+
+    pub fn iter(&self) -> CroIndexSetIter<'_, K> {
+        CroIndexSetIter { iter: self.map.iter() }
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<&K> {
+        self.map.get_index(index).map(|(key, _)| key)
+    }
+
+    pub fn first(&self) -> Option<&K> {
+        self.map.first().map(|(key, _)| key)
+    }
+
+    pub fn last(&self) -> Option<&K> {
+        self.map.last().map(|(key, _)| key)
+    }
+
+    pub fn wipe(&mut self) {
+        self.map.wipe();
+    }
+
+    pub fn move_index(&mut self, from: usize, to: usize) {
+        self.map.move_index(from, to);
+    }
+}
+
+impl<K, S> CroIndexSet<K, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn with_hasher(build_hasher: S) -> Self {
+        Self {
+            map: CroIndexMap::with_hasher(build_hasher),
+        }
+    }
+
+    pub fn with_cap(cap: usize) -> Self
+    where
+        S: Default,
+    {
+        Self {
+            map: CroIndexMap::with_cap(cap),
+        }
+    }
+
+    pub fn with_cap_and_hasher(cap: usize, build_hasher: S) -> Self {
+        Self {
+            map: CroIndexMap::with_cap_and_hasher(cap, build_hasher),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), CroInvariantError> {
+        self.map.validate()
+    }
+
+    pub fn insert(&mut self, key: K) -> bool {
+        self.insert_full(key).1
+    }
+
+    // An existing key keeps its position and the key already stored.
+    pub fn insert_full(&mut self, key: K) -> (usize, bool) {
+        let entry = self.map.entry(key);
+        let index = entry.index();
+        match entry {
+            CroIndexMapEntry::Occupied(_) => (index, false),
+            CroIndexMapEntry::Vacant(entry) => {
+                entry.insert(());
+                (index, true)
+            }
+        }
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get_key_value(key).map(|(key, _)| key)
+    }
+
+    pub fn get_full<Q>(&self, key: &Q) -> Option<(usize, &K)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get_full(key).map(|(index, key, _)| (index, key))
+    }
+
+    pub fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get_index_of(key)
+    }
+
+    pub fn swap_remove<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.swap_remove(key).is_some()
+    }
+
+    pub fn swap_take<Q>(&mut self, key: &Q) -> Option<K>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.swap_remove_entry(key).map(|(key, _)| key)
+    }
+
+    pub fn swap_remove_index(&mut self, index: usize) -> Option<K> {
+        self.map.swap_remove_index(index).map(|(key, _)| key)
+    }
+
+    pub fn shift_remove<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.shift_remove(key).is_some()
+    }
+
+    pub fn shift_take<Q>(&mut self, key: &Q) -> Option<K>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.shift_remove_entry(key).map(|(key, _)| key)
+    }
+
+    pub fn shift_remove_index(&mut self, index: usize) -> Option<K> {
+        self.map.shift_remove_index(index).map(|(key, _)| key)
+    }
+
+    pub fn pop(&mut self) -> Option<K> {
+        self.map.pop().map(|(key, _)| key)
+    }
+
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K) -> bool,
+    {
+        self.map.retain(|key, _| f(key));
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.map.reserve(additional);
+    }
+
+    pub fn sort(&mut self)
+    where
+        K: Ord,
+    {
+        self.map.sort_keys();
+    }
+
+    pub fn sort_by<F>(&mut self, mut cmp: F)
+    where
+        F: FnMut(&K, &K) -> Ordering,
+    {
+        self.map.sort_by(|k1, _, k2, _| cmp(k1, k2));
+    }
+
+    pub fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = K>,
+    {
+        self.map.extend(iter.into_iter().map(|key| (key, ())));
+    }
+}
+
+impl<K, S> Default for CroIndexSet<K, S>
+where
+    K: Eq + Hash,
+    S: Default + BuildHasher,
+{
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K, S> Clone for CroIndexSet<K, S>
+where
+    K: Eq + Hash + Clone,
+    S: BuildHasher + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<K, S> fmt::Debug for CroIndexSet<K, S>
+where
+    K: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<K, S> PartialEq for CroIndexSet<K, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<K, S> Eq for CroIndexSet<K, S> where K: Eq + Hash, S: BuildHasher {}
+
+impl<K, S> FromIterator<K> for CroIndexSet<K, S>
+where
+    K: Eq + Hash,
+    S: Default + BuildHasher,
+{
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut set = CroIndexSet::with_hasher(S::default());
+        set.extend(iter);
+        set
+    }
+}
+
+impl<K, S> Extend<K> for CroIndexSet<K, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        CroIndexSet::extend(self, iter);
+    }
+}
+
+impl<'a, K, S> IntoIterator for &'a CroIndexSet<K, S> {
+    type Item = &'a K;
+    type IntoIter = CroIndexSetIter<'a, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, S> IntoIterator for CroIndexSet<K, S> {
+    type Item = K;
+    type IntoIter = CroIndexSetIntoIter<K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        CroIndexSetIntoIter {
+            iter: self.map.into_iter(),
+        }
+    }
+}
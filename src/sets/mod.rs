@@ -1,4 +1,14 @@
 mod hash_set;
 mod btree_set;
-pub use btree_set::CroBTreeSet;
-pub use hash_set::CroHashSet;
+mod index_set;
+pub use btree_set::{
+    CroBTreeSet, CroBTreeSetDifference, CroBTreeSetIntersectAll, CroBTreeSetIntersection,
+    CroBTreeSetIntoIter, CroBTreeSetIter, CroBTreeSetRange, CroBTreeSetSymmetricDifference,
+    CroBTreeSetUnion, CroBTreeSetUnionAll,
+};
+pub use hash_set::{
+    CroHashSet, CroHashSetDifference, CroHashSetDrain, CroHashSetIntersectAll, CroHashSetIntersection,
+    CroHashSetIntoIter, CroHashSetIter, CroHashSetSymmetricDifference, CroHashSetUnion,
+    CroHashSetUnionAll,
+};
+pub use index_set::{CroIndexSet, CroIndexSetIntoIter, CroIndexSetIter};
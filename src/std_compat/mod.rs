@@ -0,0 +1,128 @@
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+
+use crate::cmp::CroNaturalOrder;
+use crate::maps::{CroBTree, CroIndexMap, CroMap};
+use crate::sequences::{CroLList, CroQue, CroVec};
+use crate::sets::{CroBTreeSet, CroHashSet, CroIndexSet};
+pub use crate::traits::Collection;
+
+// Code written against std collections ports by swapping its imports for
+// these aliases and bringing `StdCompat` and `StdCapacity` into scope.
+pub type Vec<T> = CroVec<T>;
+pub type VecDeque<T> = CroQue<T>;
+pub type LinkedList<T> = CroLList<T>;
+pub type HashMap<K, V, S = RandomState> = CroMap<K, V, S>;
+pub type HashSet<K, S = RandomState> = CroHashSet<K, S>;
+pub type BTreeMap<K, V, const B: usize = 6, C = CroNaturalOrder> = CroBTree<K, V, B, (), C>;
+pub type BTreeSet<K, C = CroNaturalOrder> = CroBTreeSet<K, C>;
+pub type IndexMap<K, V, S = RandomState> = CroIndexMap<K, V, S>;
+pub type IndexSet<K, S = RandomState> = CroIndexSet<K, S>;
+
+// Both spellings of the length and clearing methods on every container: the
+// std ones and the Cro ones, for the containers that lack some of the latter.
+pub trait StdCompat: Collection {
+    fn size(&self) -> usize {
+        self.len()
+    }
+
+    fn clear(&mut self) {
+        self.wipe();
+    }
+}
+
+impl<T: Collection> StdCompat for T {}
+
+// Both spellings of the capacity methods, only on containers that reserve
+// room ahead of their length. The trees and the linked list allocate per
+// entry, so they have no capacity to report.
+pub trait StdCapacity: Collection + Sized {
+    fn with_cap(cap: usize) -> Self;
+
+    fn cap(&self) -> usize;
+
+    fn capacity(&self) -> usize {
+        self.cap()
+    }
+
+    fn with_capacity(cap: usize) -> Self {
+        Self::with_cap(cap)
+    }
+}
+
+impl<T> StdCapacity for CroVec<T> {
+    fn with_cap(cap: usize) -> Self {
+        CroVec::with_cap(cap)
+    }
+
+    fn cap(&self) -> usize {
+        CroVec::cap(self)
+    }
+}
+
+impl<T: fmt::Debug> StdCapacity for CroQue<T> {
+    fn with_cap(cap: usize) -> Self {
+        CroQue::with_cap(cap)
+    }
+
+    fn cap(&self) -> usize {
+        CroQue::cap(self)
+    }
+}
+
+impl<K, V, S> StdCapacity for CroMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    fn with_cap(cap: usize) -> Self {
+        CroMap::with_cap(cap)
+    }
+
+    fn cap(&self) -> usize {
+        CroMap::cap(self)
+    }
+}
+
+impl<K, V, S> StdCapacity for CroIndexMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    fn with_cap(cap: usize) -> Self {
+        CroIndexMap::with_cap(cap)
+    }
+
+    fn cap(&self) -> usize {
+        CroIndexMap::cap(self)
+    }
+}
+
+impl<K, S> StdCapacity for CroHashSet<K, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    fn with_cap(cap: usize) -> Self {
+        CroHashSet::with_cap(cap)
+    }
+
+    fn cap(&self) -> usize {
+        CroHashSet::cap(self)
+    }
+}
+
+impl<K, S> StdCapacity for CroIndexSet<K, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    fn with_cap(cap: usize) -> Self {
+        CroIndexSet::with_cap(cap)
+    }
+
+    fn cap(&self) -> usize {
+        CroIndexSet::cap(self)
+    }
+}
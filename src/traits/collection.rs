@@ -0,0 +1,115 @@
+use std::ops::RangeBounds;
+
+// Shared surface of every container. The containers keep their own `size`
+// or `len`; generic code goes through `len` here.
+pub trait Collection {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn wipe(&mut self);
+}
+
+// Both ends are available on every sequence, though the front of a `CroVec`
+// costs O(n).
+pub trait Sequence<T>: Collection {
+    fn push_back(&mut self, value: T);
+
+    fn pop_back(&mut self) -> Option<T>;
+
+    fn push_front(&mut self, value: T);
+
+    fn pop_front(&mut self) -> Option<T>;
+}
+
+pub trait MapEntry<'a, K, V>: Sized {
+    fn key(&self) -> &K;
+
+    fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where
+        F: FnOnce() -> V;
+
+    fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut V);
+
+    fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+}
+
+// Lookups take `&K` rather than a borrowed form, since hashed and ordered
+// maps ask different things of one.
+pub trait MapLike<K, V>: Collection {
+    type Iter<'a>: Iterator<Item = (&'a K, &'a V)>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+
+    type Entry<'a>: MapEntry<'a, K, V>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+
+    fn get(&self, key: &K) -> Option<&V>;
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V>;
+
+    fn insert(&mut self, key: K, value: V) -> Option<V>;
+
+    fn remove(&mut self, key: &K) -> Option<V>;
+
+    fn iter(&self) -> Self::Iter<'_>;
+
+    fn entry(&mut self, key: K) -> Self::Entry<'_>;
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+}
+
+pub trait OrderedMapLike<K, V>: MapLike<K, V> {
+    type Range<'a>: DoubleEndedIterator<Item = (&'a K, &'a V)>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+
+    fn first_key_value(&self) -> Option<(&K, &V)>;
+
+    fn last_key_value(&self) -> Option<(&K, &V)>;
+
+    fn pop_first(&mut self) -> Option<(K, V)>;
+
+    fn pop_last(&mut self) -> Option<(K, V)>;
+
+    fn range<R>(&self, range: R) -> Self::Range<'_>
+    where
+        R: RangeBounds<K>;
+}
+
+pub trait SetLike<K>: Collection {
+    type Iter<'a>: Iterator<Item = &'a K>
+    where
+        Self: 'a,
+        K: 'a;
+
+    fn contains(&self, key: &K) -> bool;
+
+    fn insert(&mut self, key: K) -> bool;
+
+    fn remove(&mut self, key: &K) -> bool;
+
+    fn iter(&self) -> Self::Iter<'_>;
+}
@@ -0,0 +1,500 @@
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::ops::RangeBounds;
+
+use super::{Collection, MapEntry, MapLike, OrderedMapLike, Sequence, SetLike};
+use crate::cmp::Comparator;
+use crate::maps::{
+    CroBTree, CroBTreeEntry, CroBTreeIter, CroBTreeRange, CroIndexMap, CroIndexMapEntry,
+    CroIndexMapIter, CroMap, CroMapEntry, CroMapIter, Summary,
+};
+use crate::sequences::{CroLList, CroQue, CroVec};
+use crate::sets::{
+    CroBTreeSet, CroBTreeSetIter, CroHashSet, CroHashSetIter, CroIndexSet, CroIndexSetIter,
+};
+
+impl<T> Collection for CroVec<T> {
+    fn len(&self) -> usize {
+        self.size()
+    }
+
+    fn wipe(&mut self) {
+        CroVec::wipe(self);
+    }
+}
+
+impl<T> Sequence<T> for CroVec<T> {
+    fn push_back(&mut self, value: T) {
+        self.push(value);
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        self.pop()
+    }
+
+    fn push_front(&mut self, value: T) {
+        self.insert(0, value);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.remove(0))
+        }
+    }
+}
+
+impl<T: fmt::Debug> Collection for CroQue<T> {
+    fn len(&self) -> usize {
+        self.size()
+    }
+
+    fn wipe(&mut self) {
+        CroQue::wipe(self);
+    }
+}
+
+impl<T: fmt::Debug> Sequence<T> for CroQue<T> {
+    fn push_back(&mut self, value: T) {
+        CroQue::push_back(self, value);
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        CroQue::pop_back(self)
+    }
+
+    fn push_front(&mut self, value: T) {
+        CroQue::push_front(self, value);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        CroQue::pop_front(self)
+    }
+}
+
+impl<T> Collection for CroLList<T> {
+    fn len(&self) -> usize {
+        self.size()
+    }
+
+    fn wipe(&mut self) {
+        CroLList::wipe(self);
+    }
+}
+
+impl<T> Sequence<T> for CroLList<T> {
+    fn push_back(&mut self, value: T) {
+        CroLList::push_back(self, value);
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        CroLList::pop_back(self)
+    }
+
+    fn push_front(&mut self, value: T) {
+        CroLList::push_front(self, value);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        CroLList::pop_front(self)
+    }
+}
+
+impl<K, V, S> Collection for CroMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn len(&self) -> usize {
+        self.size()
+    }
+
+    fn wipe(&mut self) {
+        CroMap::wipe(self);
+    }
+}
+
+impl<'a, K, V, S> MapEntry<'a, K, V> for CroMapEntry<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn key(&self) -> &K {
+        CroMapEntry::key(self)
+    }
+
+    fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        CroMapEntry::or_insert_with(self, default)
+    }
+
+    fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        CroMapEntry::and_modify(self, f)
+    }
+}
+
+impl<K, V, S> MapLike<K, V> for CroMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Iter<'a>
+        = CroMapIter<'a, K, V, S>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+
+    type Entry<'a>
+        = CroMapEntry<'a, K, V, S>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+
+    fn get(&self, key: &K) -> Option<&V> {
+        CroMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        CroMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        CroMap::insert(self, key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        CroMap::remove(self, key)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        CroMap::iter(self)
+    }
+
+    fn entry(&mut self, key: K) -> Self::Entry<'_> {
+        CroMap::entry(self, key)
+    }
+}
+
+impl<K, V, S> Collection for CroIndexMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn len(&self) -> usize {
+        self.size()
+    }
+
+    fn wipe(&mut self) {
+        CroIndexMap::wipe(self);
+    }
+}
+
+impl<'a, K, V, S> MapEntry<'a, K, V> for CroIndexMapEntry<'a, K, V, S> {
+    fn key(&self) -> &K {
+        CroIndexMapEntry::key(self)
+    }
+
+    fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        CroIndexMapEntry::or_insert_with(self, default)
+    }
+
+    fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        CroIndexMapEntry::and_modify(self, f)
+    }
+}
+
+impl<K, V, S> MapLike<K, V> for CroIndexMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Iter<'a>
+        = CroIndexMapIter<'a, K, V>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+
+    type Entry<'a>
+        = CroIndexMapEntry<'a, K, V, S>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+
+    fn get(&self, key: &K) -> Option<&V> {
+        CroIndexMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        CroIndexMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        CroIndexMap::insert(self, key, value)
+    }
+
+    // Keeps the order of the remaining entries.
+    fn remove(&mut self, key: &K) -> Option<V> {
+        CroIndexMap::shift_remove(self, key)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        CroIndexMap::iter(self)
+    }
+
+    fn entry(&mut self, key: K) -> Self::Entry<'_> {
+        CroIndexMap::entry(self, key)
+    }
+}
+
+impl<K, V, const B: usize, S, C> Collection for CroBTree<K, V, B, S, C> {
+    fn len(&self) -> usize {
+        CroBTree::len(self)
+    }
+
+    fn wipe(&mut self) {
+        CroBTree::wipe(self);
+    }
+}
+
+impl<'a, K, V, const B: usize, S, C> MapEntry<'a, K, V> for CroBTreeEntry<'a, K, V, B, S, C>
+where
+    S: Summary<K, V>,
+{
+    fn key(&self) -> &K {
+        CroBTreeEntry::key(self)
+    }
+
+    fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        CroBTreeEntry::or_insert_with(self, default)
+    }
+
+    fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        CroBTreeEntry::and_modify(self, f)
+    }
+}
+
+impl<K, V, const B: usize, S, C> MapLike<K, V> for CroBTree<K, V, B, S, C>
+where
+    C: Comparator<K>,
+    S: Summary<K, V>,
+{
+    type Iter<'a>
+        = CroBTreeIter<'a, K, V, B, S>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+
+    type Entry<'a>
+        = CroBTreeEntry<'a, K, V, B, S, C>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+
+    fn get(&self, key: &K) -> Option<&V> {
+        CroBTree::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        CroBTree::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        CroBTree::insert(self, key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        CroBTree::remove(self, key)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        CroBTree::iter(self)
+    }
+
+    fn entry(&mut self, key: K) -> Self::Entry<'_> {
+        CroBTree::entry(self, key)
+    }
+}
+
+impl<K, V, const B: usize, S, C> OrderedMapLike<K, V> for CroBTree<K, V, B, S, C>
+where
+    C: Comparator<K>,
+    S: Summary<K, V>,
+{
+    type Range<'a>
+        = CroBTreeRange<'a, K, V, B, S>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+
+    fn first_key_value(&self) -> Option<(&K, &V)> {
+        CroBTree::first_key_value(self)
+    }
+
+    fn last_key_value(&self) -> Option<(&K, &V)> {
+        CroBTree::last_key_value(self)
+    }
+
+    fn pop_first(&mut self) -> Option<(K, V)> {
+        CroBTree::pop_first(self)
+    }
+
+    fn pop_last(&mut self) -> Option<(K, V)> {
+        CroBTree::pop_last(self)
+    }
+
+    fn range<R>(&self, range: R) -> Self::Range<'_>
+    where
+        R: RangeBounds<K>,
+    {
+        CroBTree::range(self, range)
+    }
+}
+
+impl<K, S> Collection for CroHashSet<K, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn len(&self) -> usize {
+        CroHashSet::len(self)
+    }
+
+    fn wipe(&mut self) {
+        CroHashSet::wipe(self);
+    }
+}
+
+impl<K, S> SetLike<K> for CroHashSet<K, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Iter<'a>
+        = CroHashSetIter<'a, K, S>
+    where
+        Self: 'a,
+        K: 'a;
+
+    fn contains(&self, key: &K) -> bool {
+        CroHashSet::contains(self, key)
+    }
+
+    fn insert(&mut self, key: K) -> bool {
+        CroHashSet::insert(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> bool {
+        CroHashSet::remove(self, key)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        CroHashSet::iter(self)
+    }
+}
+
+impl<K, S> Collection for CroIndexSet<K, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn len(&self) -> usize {
+        CroIndexSet::len(self)
+    }
+
+    fn wipe(&mut self) {
+        CroIndexSet::wipe(self);
+    }
+}
+
+impl<K, S> SetLike<K> for CroIndexSet<K, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Iter<'a>
+        = CroIndexSetIter<'a, K>
+    where
+        Self: 'a,
+        K: 'a;
+
+    fn contains(&self, key: &K) -> bool {
+        CroIndexSet::contains(self, key)
+    }
+
+    fn insert(&mut self, key: K) -> bool {
+        CroIndexSet::insert(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> bool {
+        CroIndexSet::shift_remove(self, key)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        CroIndexSet::iter(self)
+    }
+}
+
+impl<K, C> Collection for CroBTreeSet<K, C>
+where
+    C: Comparator<K>,
+{
+    fn len(&self) -> usize {
+        CroBTreeSet::len(self)
+    }
+
+    fn wipe(&mut self) {
+        CroBTreeSet::wipe(self);
+    }
+}
+
+impl<K, C> SetLike<K> for CroBTreeSet<K, C>
+where
+    C: Comparator<K>,
+{
+    type Iter<'a>
+        = CroBTreeSetIter<'a, K>
+    where
+        Self: 'a,
+        K: 'a;
+
+    fn contains(&self, key: &K) -> bool {
+        CroBTreeSet::contains(self, key)
+    }
+
+    fn insert(&mut self, key: K) -> bool {
+        CroBTreeSet::insert(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> bool {
+        CroBTreeSet::remove(self, key)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        CroBTreeSet::iter(self)
+    }
+}
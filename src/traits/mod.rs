@@ -0,0 +1,3 @@
+mod collection;
+mod impls;
+pub use collection::{Collection, MapEntry, MapLike, OrderedMapLike, Sequence, SetLike};